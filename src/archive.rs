@@ -0,0 +1,79 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io;
+use std::path::Path;
+use zip::ZipArchive;
+use zip::result::ZipError;
+use obj::{LoadingError, ObjData};
+
+fn zip_error(e : ZipError) -> LoadingError {
+    LoadingError::Io(match e {
+        ZipError::Io(e) => e,
+        ZipError::FileNotFound => io::Error::new(io::ErrorKind::NotFound, e),
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    })
+}
+
+impl ObjData {
+    /// Load the entry `entry` of the zip archive at path `archive`.
+    ///
+    /// Entries stored without compression and deflated entries are supported. A missing
+    /// entry gives an `Io` error of kind `NotFound`, and an unsupported or malformed archive
+    /// one of kind `InvalidData`.
+    pub fn load_from_zip<P : AsRef<Path>>(archive : P, entry : &str) -> Result<ObjData,LoadingError> {
+        let mut archive = ZipArchive::new(File::open(archive)?).map_err(zip_error)?;
+        let file = archive.by_name(entry).map_err(zip_error)?;
+        let data = ObjData::load(&mut BufReader::new(file))?;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::io::{BufReader, Cursor, Read, Write};
+    use std::io;
+    use zip::ZipWriter;
+    use zip::CompressionMethod;
+    use zip::write::FileOptions;
+    use obj::*;
+
+    #[test]
+    fn load_from_zip() {
+        let mut cube = Vec::new();
+        File::open("cube.obj").unwrap().read_to_end(&mut cube).unwrap();
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("readme.txt", stored).unwrap();
+        writer.write_all(b"cube").unwrap();
+        writer.start_file("models/cube.obj", stored).unwrap();
+        writer.write_all(&cube).unwrap();
+        writer.start_file("packed.obj", deflated).unwrap();
+        writer.write_all(&cube).unwrap();
+        let zip = writer.finish().unwrap().into_inner();
+        let path = env::temp_dir().join("lwobj_load_from_zip.zip");
+        File::create(&path).unwrap().write_all(&zip).unwrap();
+
+        let expected = ObjData::load(&mut BufReader::new(File::open("cube.obj").unwrap())).ok().unwrap();
+        for entry in ["models/cube.obj", "packed.obj"].iter() {
+            let data = ObjData::load_from_zip(&path, entry).ok().unwrap();
+            assert_eq!(expected.vertices,data.vertices);
+            assert_eq!(expected.normals,data.normals);
+            assert_eq!(expected.faces,data.faces);
+            assert_eq!(expected.objects,data.objects);
+        }
+        match ObjData::load_from_zip(&path, "missing.obj") {
+            Err(LoadingError::Io(e)) => assert!(e.kind() == io::ErrorKind::NotFound),
+            _ => panic!(),
+        }
+        File::create(&path).unwrap().write_all(&zip[..zip.len()-10]).unwrap();
+        match ObjData::load_from_zip(&path, "models/cube.obj") {
+            Err(LoadingError::Io(e)) => assert!(e.kind() == io::ErrorKind::InvalidData),
+            _ => panic!(),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
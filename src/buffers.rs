@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use obj::Corner;
+use obj::ObjData;
+
+/// Number of floats per vertex in the interleaved buffers: position, normal and texcoord `(u,v)`.
+const STRIDE : usize = 8;
+
+/// Triangles indexing each attribute separately, as built by `to_split_index_buffers`.
+///
+/// The corner `k` of the triangles has the position `positions[position_indices[k]]`, the
+/// normal `normals[normal_indices[k]]` and the texture coordinates `texcoords[texcoord_indices[k]]`.
+#[derive(PartialEq, Debug, Default)]
+pub struct SplitBuffers {
+    /// List of vertex positions.
+    pub positions : Vec<(f32,f32,f32)>,
+    /// List of normals, empty if no corner has one.
+    pub normals : Vec<(f32,f32,f32)>,
+    /// List of texture coordinates, empty if no corner has some.
+    pub texcoords : Vec<(f32,f32,f32)>,
+    /// Index in `positions` of each corner, three per triangle.
+    pub position_indices : Vec<u32>,
+    /// Index in `normals` of each corner, empty if `normals` is.
+    pub normal_indices : Vec<u32>,
+    /// Index in `texcoords` of each corner, empty if `texcoords` is.
+    pub texcoord_indices : Vec<u32>,
+}
+
+impl ObjData {
+    /// Push the interleaved attributes of the corner `(v,vt,vn)` in `buffer`.
+    ///
+    /// Missing normals and texcoords are filled with zeros.
+    fn push_interleaved(&self, buffer : &mut Vec<f32>, (v,vt,vn) : Corner) {
+        let (x,y,z,_) = self.vertices[v];
+        let (nx,ny,nz) = match vn {
+            Some(i) => self.normals[i],
+            None => (0.,0.,0.),
+        };
+        let (u,tv) = match vt {
+            Some(i) => (self.texcoords[i].0, self.texcoords[i].1),
+            None => (0.,0.),
+        };
+        buffer.extend_from_slice(&[x,y,z,nx,ny,nz,u,tv]);
+    }
+
+    /// Number of corners of the faces, i.e. the sum of their lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.corner_count(), 7);
+    /// ```
+    pub fn corner_count(&self) -> usize {
+        self.faces.iter().map(|f| f.len()).sum()
+    }
+
+    /// Number of corners of the triangles of the faces, triangulated as fans, to size a
+    /// triangle index buffer before building it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.triangle_corner_count(), 9);
+    /// ```
+    pub fn triangle_corner_count(&self) -> usize {
+        self.faces.iter().map(|f| 3 * f.len().saturating_sub(2)).sum()
+    }
+
+    /// Positions, normals and texture coordinates of the corners of the triangles, as three
+    /// separate arrays of 3 floats per corner.
+    ///
+    /// Faces are triangulated as fans, so each array holds 9 floats per triangle. Missing
+    /// normals and texture coordinates are filled with zeros. The triangles with a vertex
+    /// index past the end of `vertices` are left out, and the normal and texture coordinates
+    /// indices past the end are read as missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let (positions,normals,texcoords) = data.as_triangle_soup();
+    /// assert_eq!(positions, vec![0.,0.,0., 1.,0.,0., 0.,1.,0.]);
+    /// assert_eq!(normals, vec![0.,0.,1., 0.,0.,1., 0.,0.,1.]);
+    /// assert_eq!(texcoords, vec![0.; 9]);
+    /// ```
+    pub fn as_triangle_soup(&self) -> (Vec<f32>,Vec<f32>,Vec<f32>) {
+        let size = 3 * self.triangle_corner_count();
+        let mut soup = (Vec::with_capacity(size),Vec::with_capacity(size),Vec::with_capacity(size));
+        for f in &self.faces {
+            for t in self.fan(f) {
+                for &(v,vt,vn) in &t {
+                    let (x,y,z,_) = self.vertices[v];
+                    let (nx,ny,nz) = vn.and_then(|i| self.normals.get(i).cloned()).unwrap_or((0.,0.,0.));
+                    let (u,tv,w) = vt.and_then(|i| self.texcoords.get(i).cloned()).unwrap_or((0.,0.,0.));
+                    soup.0.extend_from_slice(&[x,y,z]);
+                    soup.1.extend_from_slice(&[nx,ny,nz]);
+                    soup.2.extend_from_slice(&[u,tv,w]);
+                }
+            }
+        }
+        soup
+    }
+
+    /// Build a triangle index buffer per attribute, keeping the independent indexing of the
+    /// positions, normals and texture coordinates instead of unifying the corners.
+    ///
+    /// Faces are triangulated as fans. The attributes are kept as they are, the weights of
+    /// the vertices being dropped. If only some corners have normals (resp. texture
+    /// coordinates), the other ones refer to a null one appended to `normals` (resp. `texcoords`).
+    /// As with `as_triangle_soup`, the triangles with a vertex index past the end of `vertices`
+    /// are left out, and the normal and texture coordinates indices past the end are read as missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let buffers = data.to_split_index_buffers();
+    /// assert_eq!(buffers.position_indices, vec![0,1,2,0,2,3]);
+    /// assert_eq!(buffers.normals, vec![(0.,0.,1.), (0.,0.,0.)]);
+    /// assert_eq!(buffers.normal_indices, vec![0,0,0,0,0,1]);
+    /// assert!(buffers.texcoords.is_empty() && buffers.texcoord_indices.is_empty());
+    /// ```
+    pub fn to_split_index_buffers(&self) -> SplitBuffers {
+        let triangles : Vec<[Corner;3]> = self.faces.iter().flat_map(|f| self.fan(f)).collect();
+        let normal = |c : &Corner| c.2.filter(|&n| n < self.normals.len());
+        let texcoord = |c : &Corner| c.1.filter(|&t| t < self.texcoords.len());
+        let corners = || triangles.iter().flat_map(|t| t.iter());
+        let has_normals = corners().any(|c| normal(c).is_some());
+        let has_texcoords = corners().any(|c| texcoord(c).is_some());
+        let mut buffers = SplitBuffers {
+            positions : (0..self.vertices.len()).map(|v| self.position(v)).collect(),
+            normals : if has_normals {self.normals.clone()} else {Vec::new()},
+            texcoords : if has_texcoords {self.texcoords.clone()} else {Vec::new()},
+            ..Default::default()
+        };
+        let size = 3 * triangles.len();
+        buffers.position_indices.reserve(size);
+        if has_normals {
+            buffers.normal_indices.reserve(size);
+            if corners().any(|c| normal(c).is_none()) {
+                buffers.normals.push((0.,0.,0.));
+            }
+        }
+        if has_texcoords {
+            buffers.texcoord_indices.reserve(size);
+            if corners().any(|c| texcoord(c).is_none()) {
+                buffers.texcoords.push((0.,0.,0.));
+            }
+        }
+        for c in corners() {
+            buffers.position_indices.push(c.0 as u32);
+            if has_normals {
+                buffers.normal_indices.push(normal(c).unwrap_or(self.normals.len()) as u32);
+            }
+            if has_texcoords {
+                buffers.texcoord_indices.push(texcoord(c).unwrap_or(self.texcoords.len()) as u32);
+            }
+        }
+        buffers
+    }
+
+    /// Build one interleaved vertex buffer and one triangle index buffer per material.
+    ///
+    /// Each vertex of a buffer is made of 8 floats `x,y,z,nx,ny,nz,u,v`,
+    /// missing normals and texcoords are filled with zeros.
+    /// Faces are triangulated as fans and identical `(v,vt,vn)` corners share the same vertex.
+    /// Batches are ordered by the first face using their material, and the name is `None`
+    /// for faces without material.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nusemtl red\nf 1 2 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let batches = data.to_material_batches();
+    /// assert_eq!(batches.len(), 1);
+    /// assert_eq!(batches[0].0, Some(String::from("red")));
+    /// assert_eq!(batches[0].1.len(), 4*8);
+    /// assert_eq!(batches[0].2, vec![0,1,2,0,2,3]);
+    /// ```
+    pub fn to_material_batches(&self) -> Vec<(Option<String>,Vec<f32>,Vec<u32>)> {
+        let mut batches : Vec<(Option<usize>,Vec<f32>,Vec<u32>)> = Vec::new();
+        let mut corners : Vec<HashMap<Corner,u32>> = Vec::new();
+        for (i,f) in self.faces.iter().enumerate() {
+            let material = self.face_material(i);
+            let b = match batches.iter().position(|b| b.0 == material) {
+                Some(b) => b,
+                None => {
+                    batches.push((material,Vec::new(),Vec::new()));
+                    corners.push(HashMap::new());
+                    batches.len()-1
+                },
+            };
+            let mut indexes = Vec::new();
+            for c in f {
+                let next = (batches[b].1.len() / STRIDE) as u32;
+                let index = *corners[b].entry(*c).or_insert(next);
+                if index == next {
+                    self.push_interleaved(&mut batches[b].1, *c);
+                }
+                indexes.push(index);
+            }
+            for k in 1..indexes.len().saturating_sub(1) {
+                batches[b].2.extend_from_slice(&[indexes[0],indexes[k],indexes[k+1]]);
+            }
+        }
+        batches.into_iter().map(|(m,vertices,indexes)| {
+            (m.map(|m| self.materials[m].clone()),vertices,indexes)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use obj::*;
+
+    #[test]
+    fn corner_count() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(36,data.corner_count());
+        assert_eq!(36,data.triangle_corner_count());
+
+        data.faces.push(vec![(0,None,None), (1,None,None), (2,None,None), (3,None,None), (4,None,None)]);
+        data.faces.push(vec![(0,None,None), (1,None,None)]);
+        assert_eq!(43,data.corner_count());
+        assert_eq!(45,data.triangle_corner_count());
+        let indexes : usize = data.to_material_batches().iter().map(|b| b.2.len()).sum();
+        assert_eq!(indexes,data.triangle_corner_count());
+    }
+
+    #[test]
+    fn to_split_index_buffers() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let buffers = data.to_split_index_buffers();
+        assert_eq!(8,buffers.positions.len());
+        assert_eq!(6,buffers.normals.len());
+        assert!(buffers.texcoords.is_empty() && buffers.texcoord_indices.is_empty());
+        assert_eq!(36,buffers.position_indices.len());
+        assert_eq!(36,buffers.normal_indices.len());
+        // Each triangle refers to the normal of its side for its three corners
+        for (t,n) in buffers.normal_indices.chunks(3).enumerate() {
+            assert!(n.iter().all(|&i| i == n[0]));
+            let normal = buffers.normals[n[0] as usize];
+            assert_eq!(data.face_normal(t),normal);
+        }
+        let mut used : Vec<u32> = buffers.normal_indices.clone();
+        used.sort_unstable();
+        used.dedup();
+        assert_eq!(vec![0,1,2,3,4,5],used);
+        // First triangle `f 2//1 4//1 1//1`
+        assert_eq!(&[1,3,0],&buffers.position_indices[..3]);
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        vt 0.5 0.25
+        f 1/1 2 3"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let buffers = data.to_split_index_buffers();
+        assert!(buffers.normals.is_empty() && buffers.normal_indices.is_empty());
+        assert_eq!(vec![(0.5,0.25,0.),(0.,0.,0.)],buffers.texcoords);
+        assert_eq!(vec![0,1,1],buffers.texcoord_indices);
+    }
+
+    #[test]
+    fn as_triangle_soup() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let (positions,normals,texcoords) = data.as_triangle_soup();
+        assert_eq!(36*3,positions.len());
+        assert_eq!(positions.len(),normals.len());
+        assert_eq!(positions.len(),texcoords.len());
+        assert!(texcoords.iter().all(|&t| t == 0.));
+        // First triangle `f 2//1 4//1 1//1`
+        assert_eq!(&[1.,-1.,1., -1.,-1.,-1., 1.,-1.,-1.],&positions[..9]);
+        assert_eq!(&[0.,-1.,0.],&normals[6..9]);
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0.5 0.25 1
+        f 1/1 2 3 4"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let (positions,normals,texcoords) = data.as_triangle_soup();
+        assert_eq!(18,positions.len());
+        assert_eq!(&[0.,0.,0., 1.,1.,0., 0.,1.,0.],&positions[9..]);
+        assert!(normals.iter().all(|&n| n == 0.));
+        assert_eq!(&[0.5,0.25,1., 0.,0.,0., 0.,0.,0.],&texcoords[9..]);
+    }
+
+    #[test]
+    fn to_material_batches() {
+        let obj_str =
+        r#"mtllib scene.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        vn 0 0 1
+        vt 0.5 0.5
+        usemtl red
+        f 1//1 2//1 3//1 4//1
+        usemtl blue
+        f 1/1 2/1 5/1
+        usemtl red
+        f 2//1 3//1 5//1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let batches = data.to_material_batches();
+        assert_eq!(2,batches.len());
+
+        let (ref name,ref vertices,ref indexes) = batches[0];
+        assert_eq!(&Some(String::from("red")),name);
+        assert_eq!(5*8,vertices.len());
+        assert_eq!(&vec![0,1,2,0,2,3,1,2,4],indexes);
+        assert_eq!(&[1.,1.,0.,0.,0.,1.,0.,0.],&vertices[16..24]);
+
+        let (ref name,ref vertices,ref indexes) = batches[1];
+        assert_eq!(&Some(String::from("blue")),name);
+        assert_eq!(3*8,vertices.len());
+        assert_eq!(&vec![0,1,2],indexes);
+        assert_eq!(&[0.,0.,1.,0.,0.,0.,0.5,0.5],&vertices[16..24]);
+    }
+
+    #[test]
+    fn indices_past_the_end() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        vn 0 0 1
+        f 1//1 2//2 3//1 4
+        f 3 2 5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let (positions,normals,texcoords) = data.as_triangle_soup();
+        assert_eq!(vec![0.,0.,0., 1.,0.,0., 1.,1.,0.],positions);
+        assert_eq!(vec![0.,0.,1., 0.,0.,0., 0.,0.,1.],normals);
+        assert_eq!(vec![0.; 9],texcoords);
+
+        let buffers = data.to_split_index_buffers();
+        assert_eq!(vec![0,1,2],buffers.position_indices);
+        assert_eq!(vec![(0.,0.,1.),(0.,0.,0.)],buffers.normals);
+        assert_eq!(vec![0,1,0],buffers.normal_indices);
+        assert!(buffers.texcoords.is_empty() && buffers.texcoord_indices.is_empty());
+    }
+}
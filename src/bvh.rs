@@ -0,0 +1,458 @@
+use geometry::*;
+use obj::ObjData;
+
+/// Maximum number of triangles in a leaf of a `Bvh`.
+const LEAF_SIZE : usize = 4;
+
+/// Distance along the normal from which the occlusion rays of a vertex start.
+const AO_RAY_OFFSET : f32 = 1e-4;
+
+/// Intersection of a ray with the faces of an `ObjData`.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Hit {
+    /// Index of the face hit.
+    pub face : usize,
+    /// Parameter `t` of the hit point `origin + t*dir`, the distance if `dir` is normalized.
+    pub distance : f32,
+    /// Barycentric coordinates of the hit point in the triangle of the face hit.
+    pub barycentric : (f32,f32,f32),
+}
+
+/// A node of a `Bvh`, either an inner node with two children or a leaf with triangles.
+struct Node {
+    min : Vec3,
+    max : Vec3,
+    /// Index of the first child, the second one following it, or of the first triangle.
+    start : usize,
+    /// Number of triangles, `0` for an inner node.
+    count : usize,
+}
+
+/// A bounding volume hierarchy over the triangulated faces of an `ObjData`.
+///
+/// It is built by `ObjData::build_bvh`. It keeps the positions of the triangles for
+/// `intersect`, while `raycast` must be given the same `ObjData`.
+pub struct Bvh {
+    nodes : Vec<Node>,
+    /// Vertex indices of the triangles with the index of their face.
+    triangles : Vec<([usize;3],usize)>,
+    /// Positions of the vertices of the triangles when the hierarchy was built.
+    positions : Vec<[Vec3;3]>,
+}
+
+/// Next value of the xorshift64* generator of state `state`, mapped to `[0,1)`.
+fn next_random(state : &mut u64) -> f32 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    (state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Intersect the ray `origin + t*dir`, `t > 0`, with a triangle, returning `(t,u,v)`.
+fn intersect_triangle(origin : Vec3, dir : Vec3, tri : [Vec3;3]) -> Option<(f32,f32,f32)> {
+    let e1 = sub(tri[1],tri[0]);
+    let e2 = sub(tri[2],tri[0]);
+    let p = cross(dir,e2);
+    let det = dot(e1,p);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let s = sub(origin,tri[0]);
+    let u = dot(s,p) / det;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = cross(s,e1);
+    let v = dot(dir,q) / det;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let t = dot(e2,q) / det;
+    if t <= 1e-6 {
+        return None;
+    }
+    Some((t,u,v))
+}
+
+/// Test if the ray hits the box before `t_max`.
+fn intersect_box(origin : Vec3, inv_dir : Vec3, min : Vec3, max : Vec3, t_max : f32) -> bool {
+    let mut t0 = 0f32;
+    let mut t1 = t_max;
+    for &(o,i,lo,hi) in [(origin.0,inv_dir.0,min.0,max.0),
+                         (origin.1,inv_dir.1,min.1,max.1),
+                         (origin.2,inv_dir.2,min.2,max.2)].iter() {
+        let mut near = (lo - o) * i;
+        let mut far = (hi - o) * i;
+        if near > far {
+            ::std::mem::swap(&mut near, &mut far);
+        }
+        // NaN appears when the origin lies on a slab with a null direction.
+        if !near.is_nan() {
+            t0 = t0.max(near);
+        }
+        if !far.is_nan() {
+            t1 = t1.min(far);
+        }
+        if t0 > t1 {
+            return false;
+        }
+    }
+    true
+}
+
+impl Bvh {
+    fn triangle(&self, data : &ObjData, i : usize) -> [Vec3;3] {
+        let t = self.triangles[i].0;
+        [data.position(t[0]),data.position(t[1]),data.position(t[2])]
+    }
+
+    fn bounds(&self, data : &ObjData, start : usize, end : usize) -> (Vec3,Vec3) {
+        let vertices = self.triangles[start..end].iter().flat_map(|t| t.0.iter().cloned());
+        data.bounds(vertices).unwrap()
+    }
+
+    /// Build the subtree of the triangles `start..end` at the node `node`.
+    fn build(&mut self, data : &ObjData, node : usize, start : usize, end : usize) {
+        let (min,max) = self.bounds(data, start, end);
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+        if end - start <= LEAF_SIZE {
+            self.nodes[node].start = start;
+            self.nodes[node].count = end - start;
+            return;
+        }
+        // Split at the median of the centroids along the longest axis.
+        let extent = sub(max,min);
+        let axis = if extent.0 >= extent.1 && extent.0 >= extent.2 {0} else if extent.1 >= extent.2 {1} else {2};
+        let key = |t : &([usize;3],usize)| {
+            let c = t.0.iter().fold((0.,0.,0.), |c,&v| add(c,data.position(v)));
+            match axis {
+                0 => c.0,
+                1 => c.1,
+                _ => c.2,
+            }
+        };
+        self.triangles[start..end].sort_by(|a,b| key(a).partial_cmp(&key(b)).unwrap_or(::std::cmp::Ordering::Equal));
+        let mid = (start + end) / 2;
+        let left = self.nodes.len();
+        for _ in 0..2 {
+            self.nodes.push(Node {min, max, start : 0, count : 0});
+        }
+        self.nodes[node].start = left;
+        self.build(data, left, start, mid);
+        self.build(data, left+1, mid, end);
+    }
+
+    /// Find the nearest intersection of the ray with the triangles, whose positions are given
+    /// by `triangle`.
+    fn nearest<F : Fn(usize) -> [Vec3;3]>(&self, origin : Vec3, dir : Vec3, triangle : F) -> Option<Hit> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let inv_dir = (1./dir.0, 1./dir.1, 1./dir.2);
+        let mut best : Option<Hit> = None;
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            let t_max = best.map_or(f32::INFINITY, |h| h.distance);
+            if !intersect_box(origin, inv_dir, node.min, node.max, t_max) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.start);
+                stack.push(node.start+1);
+                continue;
+            }
+            for i in node.start..node.start+node.count {
+                if let Some((t,u,v)) = intersect_triangle(origin, dir, triangle(i)) {
+                    let closer = match best {
+                        Some(h) => t < h.distance || (t == h.distance && self.triangles[i].1 < h.face),
+                        None => true,
+                    };
+                    if closer {
+                        best = Some(Hit {face : self.triangles[i].1, distance : t, barycentric : (1.-u-v,u,v)});
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Find the nearest intersection of the ray `origin + t*dir`, `t > 0`, with the faces of `data`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let bvh = data.build_bvh();
+    /// let hit = bvh.raycast(&data, (0.,5.,0.2), (0.,-1.,0.)).unwrap();
+    /// assert_eq!(hit.distance, 4.);
+    /// ```
+    pub fn raycast(&self, data : &ObjData, origin : (f32,f32,f32), dir : (f32,f32,f32)) -> Option<Hit> {
+        self.nearest(origin, dir, |i| self.triangle(data, i))
+    }
+
+    /// Find the nearest intersection of the ray `origin + t*dir`, `t > 0`, with the faces the
+    /// hierarchy was built from, using the positions of their vertices at that time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let bvh = ObjData::load(&mut input).ok().unwrap().build_bvh();
+    /// let hit = bvh.intersect((0.,5.,0.2), (0.,-1.,0.)).unwrap();
+    /// assert_eq!(hit.distance, 4.);
+    /// assert!(bvh.intersect((0.,5.,0.), (0.,1.,0.)).is_none());
+    /// ```
+    pub fn intersect(&self, origin : (f32,f32,f32), dir : (f32,f32,f32)) -> Option<Hit> {
+        self.nearest(origin, dir, |i| self.positions[i])
+    }
+}
+
+impl ObjData {
+    /// Vertex indices of the triangles of the faces, triangulated as fans, with their face.
+    /// The triangles with a vertex index past the end of `vertices` are left out.
+    fn face_triangles(&self) -> Vec<([usize;3],usize)> {
+        let mut triangles = Vec::new();
+        for (i,f) in self.faces.iter().enumerate() {
+            for t in self.fan(f) {
+                triangles.push(([t[0].0,t[1].0,t[2].0],i));
+            }
+        }
+        triangles
+    }
+
+    /// Find the nearest intersection of the ray `origin + t*dir`, `t > 0`, with the faces.
+    ///
+    /// Every triangle is tested, see `build_bvh` to cast many rays. The triangles with a vertex
+    /// index past the end of `vertices` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let hit = data.raycast((0.,5.,0.2), (0.,-1.,0.)).unwrap();
+    /// assert_eq!(hit.distance, 4.);
+    /// assert!(data.raycast((0.,5.,0.), (0.,1.,0.)).is_none());
+    /// ```
+    pub fn raycast(&self, origin : (f32,f32,f32), dir : (f32,f32,f32)) -> Option<Hit> {
+        let mut best : Option<Hit> = None;
+        for (t,face) in self.face_triangles() {
+            let tri = [self.position(t[0]),self.position(t[1]),self.position(t[2])];
+            if let Some((t,u,v)) = intersect_triangle(origin, dir, tri) {
+                if best.map_or(true, |h| t < h.distance) {
+                    best = Some(Hit {face, distance : t, barycentric : (1.-u-v,u,v)});
+                }
+            }
+        }
+        best
+    }
+
+    /// Ambient occlusion of each vertex, the fraction of `samples` rays cast from it that escape the mesh.
+    ///
+    /// The rays are spread uniformly over the hemisphere around the vertex normal, the
+    /// area-weighted mean of the normals of its faces, using a pseudo-random generator
+    /// initialized with `seed`. Vertices without faces, or without a normal, get `1`. The faces
+    /// with a vertex index past the end of `vertices` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.compute_ao(64, 1), vec![1.; 8]);
+    /// ```
+    pub fn compute_ao(&self, samples : usize, seed : u64) -> Vec<f32> {
+        let mut normals = vec![(0.,0.,0.); self.vertices.len()];
+        for (i,f) in self.faces.iter().enumerate() {
+            if f.iter().any(|&(v,_,_)| v >= self.vertices.len()) {
+                continue;
+            }
+            let n = scale(self.face_normal(i),self.face_area(i));
+            for &(v,_,_) in f {
+                normals[v] = add(normals[v],n);
+            }
+        }
+        let bvh = self.build_bvh();
+        let mut state = seed ^ 0x9e37_79b9_7f4a_7c15;
+        if state == 0 {
+            state = 1;
+        }
+        normals.iter().enumerate().map(|(v,&n)| {
+            let l = length(n);
+            if l == 0. || samples == 0 {
+                return 1.;
+            }
+            let n = scale(n,1./l);
+            let origin = add(self.position(v),scale(n,AO_RAY_OFFSET));
+            let mut unoccluded = 0;
+            for _ in 0..samples {
+                let z = 2.*next_random(&mut state) - 1.;
+                let phi = 2.*::std::f32::consts::PI*next_random(&mut state);
+                let r = (1. - z*z).max(0.).sqrt();
+                let mut dir = (r*phi.cos(), r*phi.sin(), z);
+                if dot(dir,n) < 0. {
+                    dir = scale(dir,-1.);
+                }
+                if bvh.raycast(self, origin, dir).is_none() {
+                    unoccluded += 1;
+                }
+            }
+            unoccluded as f32 / samples as f32
+        }).collect()
+    }
+
+    /// Build a bounding volume hierarchy over the triangulated faces to speed up ray casts.
+    ///
+    /// The hierarchy refers to the vertices, so it must be rebuilt if they change. The triangles
+    /// with a vertex index past the end of `vertices` are left out.
+    pub fn build_bvh(&self) -> Bvh {
+        let mut bvh = Bvh {nodes : Vec::new(), triangles : self.face_triangles(), positions : Vec::new()};
+        if !bvh.triangles.is_empty() {
+            bvh.nodes.push(Node {min : (0.,0.,0.), max : (0.,0.,0.), start : 0, count : 0});
+            let end = bvh.triangles.len();
+            bvh.build(self, 0, 0, end);
+        }
+        bvh.positions = (0..bvh.triangles.len()).map(|i| bvh.triangle(self, i)).collect();
+        bvh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use obj::*;
+
+    #[test]
+    fn bvh_raycast() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let bvh = data.build_bvh();
+
+        let hit = bvh.raycast(&data, (0.2,0.3,5.), (0.,0.,-1.)).unwrap();
+        assert_eq!(4.,hit.distance);
+        assert!(hit.face == 3 || hit.face == 9);
+
+        let mut count = 0;
+        for i in 0..20 {
+            for j in 0..20 {
+                let origin = (-3. + 0.3*i as f32, -3. + 0.3*j as f32, 4.);
+                for dir in [(0.,0.,-1.), (0.3,-0.2,-1.), (-0.5,0.5,-0.7)].iter() {
+                    let expected = data.raycast(origin, *dir);
+                    let hit = bvh.raycast(&data, origin, *dir);
+                    assert_eq!(expected.map(|h| h.distance),hit.map(|h| h.distance));
+                    if hit.is_some() {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        assert!(count > 0);
+        assert!(bvh.raycast(&data, (0.,0.,4.), (0.,0.,1.)).is_none());
+        assert!(ObjData::new().build_bvh().raycast(&data, (0.,0.,4.), (0.,0.,-1.)).is_none());
+    }
+
+    #[test]
+    fn bvh_raycast_face() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let bvh = data.build_bvh();
+
+        // Top face `f 8//2 6//2 5//2`
+        let hit = bvh.raycast(&data, (0.5,5.,-0.5), (0.,-1.,0.)).unwrap();
+        assert_eq!(1,hit.face);
+        assert_eq!(4.,hit.distance);
+        let (a,b,c) = hit.barycentric;
+        assert!((a + b + c - 1.).abs() < 1e-6);
+        let p = [8,6,5].iter().zip([a,b,c].iter()).fold((0.,0.,0.), |p,(&v,&w)| {
+            let (x,y,z,_) = data.vertices[v-1];
+            (p.0 + w*x, p.1 + w*y, p.2 + w*z)
+        });
+        assert!((p.0 - 0.5).abs() < 1e-6 && (p.1 - 1.).abs() < 1e-6 && (p.2 + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bvh_intersect() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        let bvh = data.build_bvh();
+
+        // Top face `f 8//2 6//2 5//2`
+        let hit = bvh.intersect((0.5,5.,-0.5), (0.,-1.,0.)).unwrap();
+        assert_eq!(1,hit.face);
+        assert_eq!(4.,hit.distance);
+        assert_eq!(bvh.raycast(&data, (0.5,5.,-0.5), (0.,-1.,0.)),Some(hit));
+        let (a,b,c) = hit.barycentric;
+        assert!((a + b + c - 1.).abs() < 1e-6);
+
+        // The hierarchy keeps the positions it was built with
+        data.vertices.clear();
+        assert_eq!(Some(hit),bvh.intersect((0.5,5.,-0.5), (0.,-1.,0.)));
+        assert!(bvh.intersect((0.5,5.,-0.5), (0.,1.,0.)).is_none());
+        assert!(ObjData::new().build_bvh().intersect((0.,0.,4.), (0.,0.,-1.)).is_none());
+    }
+
+    #[test]
+    fn compute_ao() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let exposed = data.compute_ao(128, 7);
+        assert_eq!(vec![1.; 8],exposed);
+
+        // Turn the cube into a box open at the top, seen from the inside
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut open = ObjData::load(&mut input).ok().unwrap();
+        let top : Vec<usize> = (0..open.faces.len()).filter(|&i| open.face_normal(i).1 > 0.5).collect();
+        assert_eq!(2,top.len());
+        open.faces = open.faces.iter().enumerate().filter(|&(i,_)| !top.contains(&i)).map(|(_,f)| f.clone()).collect();
+        for f in &mut open.faces {
+            f.reverse();
+        }
+        let ao = open.compute_ao(128, 7);
+        let bottom = (0..open.vertices.len()).find(|&v| open.vertices[v].1 < 0.).unwrap();
+        assert!(ao[bottom] > 0.);
+        assert!(ao[bottom] < 0.5);
+        assert!(ao[bottom] < exposed[bottom]);
+        assert_eq!(ao,open.compute_ao(128, 7));
+    }
+
+    #[test]
+    fn indices_past_the_end() {
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\nf 1 2 3 4".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let bvh = data.build_bvh();
+        let hit = bvh.intersect((0.25,0.25,1.), (0.,0.,-1.)).unwrap();
+        assert_eq!(1,hit.face);
+        assert_eq!(Some(hit),bvh.raycast(&data, (0.25,0.25,1.), (0.,0.,-1.)));
+        assert_eq!(Some(hit),data.raycast((0.25,0.25,1.), (0.,0.,-1.)));
+        assert_eq!(vec![1.; 3],data.compute_ao(16, 7));
+    }
+}
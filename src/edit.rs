@@ -0,0 +1,2935 @@
+use std::collections::HashMap;
+use geometry::*;
+use obj::{Corner, Face, Group, LoadingError, ObjData, Object};
+
+/// Size of the simulated vertex cache used to reorder faces.
+const VERTEX_CACHE_SIZE : usize = 32;
+
+/// Maximum number of passes of `subdivide_adaptive`, each one splitting a triangle in four.
+const MAX_SUBDIVISIONS : usize = 8;
+
+/// Precision to which `deduplicate_attributes` compares texture coordinates and normals.
+const ATTRIBUTE_EPSILON : f32 = 1e-6;
+
+/// Uniform scaling followed by a translation, mapping a point `p` to `p*scale + translation`.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Transform {
+    pub scale : f32,
+    pub translation : (f32,f32,f32),
+}
+
+/// Up axis convention of a coordinate system, both being right-handed.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Axis {
+    /// The `y` axis points up, `-z` forward.
+    Yup,
+    /// The `z` axis points up, `y` forward.
+    Zup,
+}
+
+/// Coordinate plane on which the vertices are projected by `generate_planar_uvs`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Plane {
+    /// The `(x,y)` plane, projecting along `z`.
+    XY,
+    /// The `(y,z)` plane, projecting along `x`.
+    YZ,
+    /// The `(x,z)` plane, projecting along `y`.
+    XZ,
+}
+
+/// Point of the bounding box of the vertices moved to the origin by `recenter`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Anchor {
+    /// The center of the box.
+    Center,
+    /// The center of the bottom side of the box, `y` pointing up.
+    BottomCenter,
+    /// The origin itself, leaving the vertices in place.
+    Origin,
+    /// The corner of the box with the smallest coordinates.
+    Min,
+    /// The corner of the box with the largest coordinates.
+    Max,
+}
+
+impl ObjData {
+    /// Reorder the vertices by their first use in the faces, remapping the faces accordingly.
+    ///
+    /// Vertices used together end up close in memory, which improves the cache locality
+    /// when rendering. Unused vertices are moved at the end, keeping their relative order.
+    /// Indices past the end of `vertices` are ignored and left as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 3 1 2".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.optimize_vertex_order();
+    /// assert_eq!(data.vertices[0], (0.,1.,0.,1.));
+    /// assert_eq!(data.faces[0], vec![(0,None,None), (1,None,None), (2,None,None)]);
+    /// ```
+    pub fn optimize_vertex_order(&mut self) {
+        let mut remap : Vec<Option<usize>> = vec![None; self.vertices.len()];
+        let mut order = Vec::with_capacity(self.vertices.len());
+        for f in &self.faces {
+            for &(v,_,_) in f {
+                if remap.get(v) == Some(&None) {
+                    remap[v] = Some(order.len());
+                    order.push(v);
+                }
+            }
+        }
+        for (v,r) in remap.iter_mut().enumerate() {
+            if r.is_none() {
+                *r = Some(order.len());
+                order.push(v);
+            }
+        }
+        self.vertices = order.iter().map(|&v| self.vertices[v]).collect();
+        let remapped = |v : usize| remap.get(v).map_or(v, |r| r.unwrap());
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                c.0 = remapped(c.0);
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                c.0 = remapped(c.0);
+            }
+        }
+        for p in &mut self.points {
+            for v in p.iter_mut() {
+                *v = remapped(*v);
+            }
+        }
+    }
+
+    /// Multiply the coordinates `(x,y,z)` of every vertex by `factor`.
+    ///
+    /// Weights, normals and texture coordinates are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((1.,-2.,3.,0.5));
+    /// data.scale_uniform(2.);
+    /// assert_eq!(data.vertices[0], (2.,-4.,6.,0.5));
+    /// ```
+    pub fn scale_uniform(&mut self, factor : f32) {
+        for v in &mut self.vertices {
+            v.0 *= factor;
+            v.1 *= factor;
+            v.2 *= factor;
+        }
+    }
+
+    /// Divide the coordinates `(x,y,z)` of every vertex by its weight, which becomes `1`.
+    ///
+    /// Vertices of weight `0`, points at infinity, are left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((2.,4.,6.,2.));
+    /// data.vertices.push((1.,0.,0.,0.));
+    /// data.dehomogenize();
+    /// assert_eq!(data.vertices, vec![(1.,2.,3.,1.), (1.,0.,0.,0.)]);
+    /// ```
+    pub fn dehomogenize(&mut self) {
+        for v in &mut self.vertices {
+            if v.3 != 0. && v.3 != 1. {
+                *v = (v.0 / v.3, v.1 / v.3, v.2 / v.3, 1.);
+            }
+        }
+    }
+
+    /// Replace every vertex `(x,y,z,w)` by `f((x,y,z,w))`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((1.,2.,3.,1.));
+    /// data.map_vertices(|(x,y,z,w)| (x, y + x*x, z, w));
+    /// assert_eq!(data.vertices[0], (1.,3.,3.,1.));
+    /// ```
+    pub fn map_vertices<F : FnMut((f32,f32,f32,f32)) -> (f32,f32,f32,f32)>(&mut self, mut f : F) {
+        for v in &mut self.vertices {
+            *v = f(*v);
+        }
+    }
+
+    /// Replace every normal `(x,y,z)` by `f((x,y,z))`.
+    pub fn map_normals<F : FnMut((f32,f32,f32)) -> (f32,f32,f32)>(&mut self, mut f : F) {
+        for n in &mut self.normals {
+            *n = f(*n);
+        }
+    }
+
+    /// Replace every texture coordinate `(u,v,w)` by `f((u,v,w))`.
+    pub fn map_texcoords<F : FnMut((f32,f32,f32)) -> (f32,f32,f32)>(&mut self, mut f : F) {
+        for t in &mut self.texcoords {
+            *t = f(*t);
+        }
+    }
+
+    /// Center the vertices at the origin and scale them to fit in the cube `[-1,1]^3`.
+    ///
+    /// Returns the `Transform` applied, which `apply_inverse` undoes.
+    /// Vertices whose bounding box is a single point are only translated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((1.,2.,3.,1.));
+    /// data.vertices.push((5.,4.,3.,1.));
+    /// let t = data.normalize();
+    /// assert_eq!(data.vertices, vec![(-1.,-0.5,0.,1.), (1.,0.5,0.,1.)]);
+    /// data.apply_inverse(&t);
+    /// assert_eq!(data.vertices, vec![(1.,2.,3.,1.), (5.,4.,3.,1.)]);
+    /// ```
+    pub fn normalize(&mut self) -> Transform {
+        let (min,max) = match self.bounds(0..self.vertices.len()) {
+            Some(b) => b,
+            None => return Transform {scale : 1., translation : (0.,0.,0.)},
+        };
+        let center = scale(add(min,max),0.5);
+        let half = sub(max,center);
+        let extent = half.0.max(half.1).max(half.2);
+        let factor = if extent > 0. {1. / extent} else {1.};
+        let t = Transform {scale : factor, translation : scale(center,-factor)};
+        for v in &mut self.vertices {
+            let (x,y,z) = add(scale((v.0,v.1,v.2),t.scale),t.translation);
+            *v = (x,y,z,v.3);
+        }
+        t
+    }
+
+    /// Translate the vertices so that the point `anchor` of their bounding box is at the origin.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{Anchor, ObjData};
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((1.,2.,3.,1.));
+    /// data.vertices.push((5.,4.,3.,1.));
+    /// data.recenter(Anchor::BottomCenter);
+    /// assert_eq!(data.vertices, vec![(-2.,0.,0.,1.), (2.,2.,0.,1.)]);
+    /// ```
+    pub fn recenter(&mut self, anchor : Anchor) {
+        let (min,max) = match self.bounds(0..self.vertices.len()) {
+            Some(b) => b,
+            None => return,
+        };
+        let center = scale(add(min,max),0.5);
+        let origin = match anchor {
+            Anchor::Center => center,
+            Anchor::BottomCenter => (center.0,min.1,center.2),
+            Anchor::Origin => (0.,0.,0.),
+            Anchor::Min => min,
+            Anchor::Max => max,
+        };
+        for v in &mut self.vertices {
+            let (x,y,z) = sub((v.0,v.1,v.2),origin);
+            *v = (x,y,z,v.3);
+        }
+    }
+
+    /// Undo the transform `t` on the vertices, mapping a point `p` to `(p - translation)/scale`.
+    pub fn apply_inverse(&mut self, t : &Transform) {
+        for v in &mut self.vertices {
+            let (x,y,z) = scale(sub((v.0,v.1,v.2),t.translation),1. / t.scale);
+            *v = (x,y,z,v.3);
+        }
+    }
+
+    /// Convert the vertices from millimeters to meters.
+    pub fn to_meters_from_millimeters(&mut self) {
+        self.scale_uniform(0.001);
+    }
+
+    /// Convert the vertices from centimeters to meters.
+    pub fn to_meters_from_centimeters(&mut self) {
+        self.scale_uniform(0.01);
+    }
+
+    /// Convert the vertices from inches to meters.
+    pub fn to_meters_from_inches(&mut self) {
+        self.scale_uniform(0.0254);
+    }
+
+    /// Convert the vertices from meters to millimeters.
+    pub fn to_millimeters_from_meters(&mut self) {
+        self.scale_uniform(1000.);
+    }
+
+    /// Convert the vertices from meters to centimeters.
+    pub fn to_centimeters_from_meters(&mut self) {
+        self.scale_uniform(100.);
+    }
+
+    /// Snap the coordinates of the vertices to a grid of `2^bits` values per axis spanning their bounding box.
+    ///
+    /// Returns the bounding box `(min,max)` used, with which a coordinate `x` maps to the
+    /// integer `(x - min) / (max - min) * (2^bits - 1)`. Each coordinate moves by at most half a step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((0.,0.,0.,1.));
+    /// data.vertices.push((0.4,1.,2.,1.));
+    /// data.vertices.push((1.,1.,1.,1.));
+    /// let bounds = data.quantize_positions(1);
+    /// assert_eq!(bounds, ((0.,0.,0.),(1.,1.,2.)));
+    /// assert_eq!(data.vertices[1], (0.,1.,2.,1.));
+    /// assert_eq!(data.vertices[2], (1.,1.,2.,1.));
+    /// ```
+    pub fn quantize_positions(&mut self, bits : u8) -> ((f32,f32,f32),(f32,f32,f32)) {
+        let (min,max) = match self.bounds(0..self.vertices.len()) {
+            Some(b) => b,
+            None => return ((0.,0.,0.),(0.,0.,0.)),
+        };
+        let steps = ((1u64 << bits.min(32)) - 1) as f32;
+        let quantize = |x : f32, lo : f32, hi : f32| {
+            if hi > lo {
+                lo + ((x - lo) / (hi - lo) * steps).round() / steps * (hi - lo)
+            } else {
+                x
+            }
+        };
+        for v in &mut self.vertices {
+            v.0 = quantize(v.0,min.0,max.0);
+            v.1 = quantize(v.1,min.1,max.1);
+            v.2 = quantize(v.2,min.2,max.2);
+        }
+        (min,max)
+    }
+
+    /// Convert the vertices and normals from the up axis `from` to the up axis `to`.
+    ///
+    /// A `Zup` point `(x,y,z)` becomes `(x,z,-y)` in `Yup`, and conversely.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{Axis, ObjData};
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((1.,2.,3.,1.));
+    /// data.convert_axis(Axis::Zup, Axis::Yup);
+    /// assert_eq!(data.vertices[0], (1.,3.,-2.,1.));
+    /// ```
+    pub fn convert_axis(&mut self, from : Axis, to : Axis) {
+        let convert : fn(f32,f32,f32) -> (f32,f32,f32) = match (from,to) {
+            (Axis::Zup,Axis::Yup) => |x,y,z| (x,z,-y),
+            (Axis::Yup,Axis::Zup) => |x,y,z| (x,-z,y),
+            _ => return,
+        };
+        for v in &mut self.vertices {
+            let (x,y,z) = convert(v.0,v.1,v.2);
+            *v = (x,y,z,v.3);
+        }
+        for n in &mut self.normals {
+            *n = convert(n.0,n.1,n.2);
+        }
+    }
+
+    /// Swap the components `a` and `b` of every texture coordinates, `0` being `u`, `1` `v` and `2` `w`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` is greater than `2`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// data.texcoords.push((0.1,0.2,0.3));
+    /// data.swap_texcoord_components(1,2);
+    /// assert_eq!(data.texcoords[0], (0.1,0.3,0.2));
+    /// ```
+    pub fn swap_texcoord_components(&mut self, a : usize, b : usize) {
+        assert!(a < 3 && b < 3, "texture coordinates have 3 components, cannot swap {} and {}", a, b);
+        for t in &mut self.texcoords {
+            let mut c = [t.0,t.1,t.2];
+            c.swap(a,b);
+            *t = (c[0],c[1],c[2]);
+        }
+    }
+
+    /// Planar projection of each vertex on the plane `plane`, scaled to `[0,1]` over the
+    /// bounding box of the vertices.
+    fn planar_projection(&self, plane : Plane) -> Vec<(f32,f32,f32)> {
+        let project = |p : Vec3| match plane {
+            Plane::XY => (p.0,p.1),
+            Plane::YZ => (p.1,p.2),
+            Plane::XZ => (p.0,p.2),
+        };
+        let (min,max) = match self.bounds(0..self.vertices.len()) {
+            Some((min,max)) => (project(min),project(max)),
+            None => ((0.,0.),(0.,0.)),
+        };
+        let fit = |x : f32, lo : f32, hi : f32| if hi > lo {(x - lo) / (hi - lo)} else {0.};
+        (0..self.vertices.len()).map(|v| {
+            let (u,w) = project(self.position(v));
+            (fit(u,min.0,max.0),fit(w,min.1,max.1),0.)
+        }).collect()
+    }
+
+    /// Replace the texture coordinates by a planar projection along the up axis `axis`.
+    ///
+    /// Each vertex is projected on the plane perpendicular to `axis`, `(x,z)` for `Yup` and
+    /// `(x,y)` for `Zup`, and the projection is scaled to `[0,1]` over the bounding box of the
+    /// vertices. Vertex `i` gets the texture coordinates `i`, which every corner using it refers to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{Axis, ObjData};
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 0 5 4\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.generate_planar_uv(Axis::Yup);
+    /// assert_eq!(data.texcoords, vec![(0.,0.,0.), (1.,0.,0.), (0.,1.,0.)]);
+    /// assert_eq!(data.faces[0][2], (2,Some(2),None));
+    /// ```
+    pub fn generate_planar_uv(&mut self, axis : Axis) {
+        self.texcoords = self.planar_projection(match axis {
+            Axis::Yup => Plane::XZ,
+            Axis::Zup => Plane::XY,
+        });
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                c.1 = Some(c.0);
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                c.1 = Some(c.0);
+            }
+        }
+    }
+
+    /// Give the face corners without texture coordinates a projection on the plane `plane`.
+    ///
+    /// Each vertex is projected as in `generate_planar_uv`, scaled to `[0,1]` over the bounding
+    /// box of the vertices, and appended to `texcoords` once per vertex needing it. With `force`,
+    /// every corner gets the projection, the former texture coordinates being kept but no longer
+    /// referenced by the faces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{ObjData, Plane};
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 0 5 4\nvt 0.5 0.5\nf 1 2/1 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.generate_planar_uvs(Plane::XZ, false);
+    /// assert_eq!(data.texcoords, vec![(0.5,0.5,0.), (0.,0.,0.), (0.,1.,0.)]);
+    /// assert_eq!(data.faces[0], vec![(0,Some(1),None), (1,Some(0),None), (2,Some(2),None)]);
+    /// ```
+    pub fn generate_planar_uvs(&mut self, plane : Plane, force : bool) {
+        let projection = self.planar_projection(plane);
+        let mut added = vec![None; self.vertices.len()];
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                if c.1.is_none() || force {
+                    let texcoords = &mut self.texcoords;
+                    c.1 = Some(*added[c.0].get_or_insert_with(|| {
+                        texcoords.push(projection[c.0]);
+                        texcoords.len()-1
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Replace the texture coordinates by a box projection.
+    ///
+    /// Each face is projected on the plane perpendicular to the dominant axis of its normal,
+    /// `(y,z)` for `x`, `(x,z)` for `y` and `(x,y)` for `z`, scaled to `[0,1]` over the bounding
+    /// box of the vertices. A vertex gets one texture coordinates per plane its faces use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 2 3\nf 1 4 2".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.generate_box_uv();
+    /// assert_eq!(data.texcoords[..3].to_vec(), vec![(0.,0.,0.), (1.,0.,0.), (0.,1.,0.)]);
+    /// assert_eq!(data.faces[1][1], (3,Some(4),None));
+    /// assert_eq!(data.texcoords[4], (0.,1.,0.));
+    /// ```
+    pub fn generate_box_uv(&mut self) {
+        let (min,max) = self.bounds(0..self.vertices.len()).unwrap_or(((0.,0.,0.),(0.,0.,0.)));
+        let fit = |x : f32, lo : f32, hi : f32| if hi > lo {(x - lo) / (hi - lo)} else {0.};
+        let mut texcoords = Vec::new();
+        let mut indices : HashMap<(usize,usize),usize> = HashMap::new();
+        for i in 0..self.faces.len() {
+            let n = self.face_normal(i);
+            let axis = if n.0.abs() >= n.1.abs() && n.0.abs() >= n.2.abs() {
+                0
+            } else if n.1.abs() >= n.2.abs() {
+                1
+            } else {
+                2
+            };
+            for k in 0..self.faces[i].len() {
+                let v = self.faces[i][k].0;
+                let p = self.position(v);
+                let vt = *indices.entry((v,axis)).or_insert_with(|| {
+                    let (u,w) = match axis {
+                        0 => (fit(p.1,min.1,max.1),fit(p.2,min.2,max.2)),
+                        1 => (fit(p.0,min.0,max.0),fit(p.2,min.2,max.2)),
+                        _ => (fit(p.0,min.0,max.0),fit(p.1,min.1,max.1)),
+                    };
+                    texcoords.push((u,w,0.));
+                    texcoords.len()-1
+                });
+                self.faces[i][k].1 = Some(vt);
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                c.1 = None;
+            }
+        }
+        self.texcoords = texcoords;
+    }
+
+    /// Replace the normals by smooth ones, one per vertex, averaging the normals of the faces
+    /// around each vertex.
+    ///
+    /// With `angle_weighted`, the normal of a face counts as much as its interior angle at the
+    /// vertex, so that splitting a face in thin triangles does not change the result. Otherwise
+    /// each face counts the same. Every corner references the normal of its vertex, which is
+    /// null for a vertex used by no face or only by degenerate ones. The faces with a vertex
+    /// index past the end of `vertices` are ignored, and the corners with such an index get no
+    /// normal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.generate_vertex_normals(true);
+    /// assert_eq!(data.normals, vec![(0.,0.,1.); 3]);
+    /// assert_eq!(data.faces[0], vec![(0,None,Some(0)), (1,None,Some(1)), (2,None,Some(2))]);
+    /// ```
+    pub fn generate_vertex_normals(&mut self, angle_weighted : bool) {
+        let mut normals = vec![(0.,0.,0.); self.vertices.len()];
+        for i in 0..self.faces.len() {
+            let f = &self.faces[i];
+            if f.iter().any(|c| c.0 >= normals.len()) {
+                continue;
+            }
+            let n = self.face_normal(i);
+            for k in 0..f.len() {
+                let weight = if angle_weighted {
+                    let p = self.position(f[k].0);
+                    let prev = self.position(f[(k+f.len()-1)%f.len()].0);
+                    let next = self.position(f[(k+1)%f.len()].0);
+                    angle(sub(next,p),sub(prev,p))
+                } else {
+                    1.
+                };
+                normals[f[k].0] = add(normals[f[k].0],scale(n,weight));
+            }
+        }
+        self.normals = normals.into_iter().map(|n| {
+            let l = length(n);
+            if l > 0. {scale(n,1./l)} else {n}
+        }).collect();
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                c.2 = if c.0 < self.normals.len() {Some(c.0)} else {None};
+            }
+        }
+    }
+
+    /// Drop the texture coordinates and normals, leaving faces made of vertex indices only.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3//1";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.positions_only();
+    /// assert!(data.texcoords.is_empty() && data.normals.is_empty());
+    /// assert_eq!(data.faces[0], vec![(0,None,None), (1,None,None), (2,None,None)]);
+    /// ```
+    pub fn positions_only(&mut self) {
+        self.texcoords.clear();
+        self.normals.clear();
+        self.face_slashes.clear();
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                c.1 = None;
+                c.2 = None;
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                c.1 = None;
+            }
+        }
+    }
+
+    /// Reverse the order of the corners of every face, turning them inside out.
+    ///
+    /// Corners are moved as a whole, each vertex keeping its texture coordinates and normal.
+    /// The normals themselves are not negated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.flip_winding();
+    /// assert_eq!(data.faces[0], vec![(2,None,None), (1,None,None), (0,None,None)]);
+    /// ```
+    pub fn flip_winding(&mut self) {
+        for f in &mut self.faces {
+            f.reverse();
+        }
+        for s in &mut self.face_slashes {
+            s.reverse();
+        }
+    }
+
+    /// Reverse the order of the corners of every face, keeping the texture seams intact.
+    ///
+    /// Same as `flip_winding`, which never separates a vertex from its texture coordinates
+    /// and normal.
+    pub fn flip_winding_preserve_uv(&mut self) {
+        self.flip_winding();
+    }
+
+    /// Reverse the faces whose normal points toward the centroid of the vertices.
+    ///
+    /// A roughly convex mesh ends up with all its faces facing outward. Degenerate faces are
+    /// left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.flip_winding();
+    /// data.orient_outward();
+    /// assert!(data.check_winding().is_empty());
+    /// ```
+    pub fn orient_outward(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        let n = self.vertices.len() as f32;
+        let centroid = scale(self.vertices.iter().fold((0.,0.,0.), |c,v| add(c,(v.0,v.1,v.2))), 1./n);
+        for i in 0..self.faces.len() {
+            let f = &self.faces[i];
+            if f.is_empty() {
+                continue;
+            }
+            let center = scale(f.iter().fold((0.,0.,0.), |c,corner| add(c,self.position(corner.0))), 1./f.len() as f32);
+            if dot(self.face_normal(i),sub(center,centroid)) < 0. {
+                self.faces[i].reverse();
+                if let Some(s) = self.face_slashes.get_mut(i) {
+                    s.reverse();
+                }
+            }
+        }
+    }
+
+    /// Flip the normals pointing against the geometric normal of most of the faces using them.
+    ///
+    /// Corners whose normal still points against the geometric normal of their face, because
+    /// it is shared with faces of the opposite orientation, get a flipped copy of it. Degenerate
+    /// faces are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 -1\nf 1//1 2//1 3//1".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.fix_normal_orientation();
+    /// assert_eq!(data.normals, vec![(0.,0.,1.)]);
+    /// ```
+    pub fn fix_normal_orientation(&mut self) {
+        let face_normals : Vec<Vec3> = (0..self.faces.len()).map(|i| self.face_normal(i)).collect();
+        let mut votes = vec![0i32; self.normals.len()];
+        for (f,&n) in self.faces.iter().zip(&face_normals) {
+            for &(_,_,vn) in f {
+                if let Some(k) = vn {
+                    let d = dot(self.normals[k],n);
+                    if d < 0. {
+                        votes[k] -= 1;
+                    } else if d > 0. {
+                        votes[k] += 1;
+                    }
+                }
+            }
+        }
+        for (n,&vote) in self.normals.iter_mut().zip(&votes) {
+            if vote < 0 {
+                *n = scale(*n,-1.);
+            }
+        }
+        let mut flipped = HashMap::new();
+        for (f,&n) in self.faces.iter_mut().zip(&face_normals) {
+            for c in f.iter_mut() {
+                if let Some(k) = c.2 {
+                    if dot(self.normals[k],n) < 0. {
+                        let normals = &mut self.normals;
+                        c.2 = Some(*flipped.entry(k).or_insert_with(|| {
+                            normals.push(scale(normals[k],-1.));
+                            normals.len()-1
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Merge the vertices whose positions coincide within `epsilon`, keeping the texture
+    /// coordinates and normals of the corners as they are.
+    ///
+    /// Each group found by `duplicate_vertices` is replaced by its first vertex and the
+    /// remaining vertices are renumbered, keeping their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 0 0\nv 1 1 0\nvn 0 0 1\nvn 0 0 -1\nf 1//1 2//1 3//1\nf 4//2 5//2 3//2";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.merge_positions_keep_attributes(1e-6);
+    /// assert_eq!(data.vertices.len(), 4);
+    /// assert_eq!(data.faces[1], vec![(1,None,Some(1)), (3,None,Some(1)), (2,None,Some(1))]);
+    /// ```
+    pub fn merge_positions_keep_attributes(&mut self, epsilon : f32) {
+        let groups = self.duplicate_vertices(epsilon);
+        self.merge_vertex_groups(&groups);
+    }
+
+    /// Merge the identical texture coordinates and the identical normals, remapping the faces
+    /// to the first occurrence of each.
+    ///
+    /// Values are compared once rounded to a multiple of `1e-6`. The remaining elements keep
+    /// their relative order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 1\nvn 0 0 1\nf 1//1 2//2 3//3";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.deduplicate_attributes();
+    /// assert_eq!(data.normals, vec![(0.,0.,1.)]);
+    /// assert_eq!(data.faces[0], vec![(0,None,Some(0)), (1,None,Some(0)), (2,None,Some(0))]);
+    /// ```
+    pub fn deduplicate_attributes(&mut self) {
+        fn deduplicate(values : &mut Vec<Vec3>) -> Vec<usize> {
+            let key = |x : f32| (x / ATTRIBUTE_EPSILON).round() as i64;
+            let mut first : HashMap<(i64,i64,i64),usize> = HashMap::new();
+            let mut kept = Vec::new();
+            let remap = values.iter().map(|&v| {
+                *first.entry((key(v.0),key(v.1),key(v.2))).or_insert_with(|| {
+                    kept.push(v);
+                    kept.len()-1
+                })
+            }).collect();
+            *values = kept;
+            remap
+        }
+        let texcoords = deduplicate(&mut self.texcoords);
+        let normals = deduplicate(&mut self.normals);
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                c.1 = c.1.map(|t| texcoords.get(t).cloned().unwrap_or(t));
+                c.2 = c.2.map(|n| normals.get(n).cloned().unwrap_or(n));
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                c.1 = c.1.map(|t| texcoords.get(t).cloned().unwrap_or(t));
+            }
+        }
+    }
+
+    /// Identifier of the objects and groups of the faces using each vertex, equal for two
+    /// vertices only if their faces belong to the same objects and groups.
+    fn seam_regions(&self) -> Vec<usize> {
+        let mut regions : HashMap<(Vec<usize>,Vec<usize>),usize> = HashMap::new();
+        let mut vertex_regions = vec![Vec::new(); self.vertices.len()];
+        for (i,f) in self.faces.iter().enumerate() {
+            let objects = (0..self.objects.len()).filter(|&o| self.objects[o].primitives.contains(&i)).collect();
+            let groups = (0..self.groups.len()).filter(|&g| self.groups[g].indexes.contains(&i)).collect();
+            let n = regions.len();
+            let region = *regions.entry((objects,groups)).or_insert(n);
+            for &(v,_,_) in f {
+                if v < vertex_regions.len() && !vertex_regions[v].contains(&region) {
+                    vertex_regions[v].push(region);
+                }
+            }
+        }
+        let mut ids : HashMap<Vec<usize>,usize> = HashMap::new();
+        vertex_regions.into_iter().map(|mut r| {
+            r.sort();
+            let n = ids.len();
+            *ids.entry(r).or_insert(n)
+        }).collect()
+    }
+
+    /// Replace each group of vertices by its first one, renumbering the remaining vertices.
+    fn merge_vertex_groups(&mut self, groups : &[Vec<usize>]) {
+        let mut target : Vec<usize> = (0..self.vertices.len()).collect();
+        for group in groups {
+            for &v in &group[1..] {
+                target[v] = group[0];
+            }
+        }
+        let mut index = vec![0; self.vertices.len()];
+        let mut vertices = Vec::new();
+        for (v,&t) in target.iter().enumerate() {
+            if t == v {
+                index[v] = vertices.len();
+                vertices.push(self.vertices[v]);
+            }
+        }
+        self.vertices = vertices;
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                if c.0 < target.len() {
+                    c.0 = index[target[c.0]];
+                }
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                if c.0 < target.len() {
+                    c.0 = index[target[c.0]];
+                }
+            }
+        }
+        for p in &mut self.points {
+            for v in p.iter_mut() {
+                if *v < target.len() {
+                    *v = index[target[*v]];
+                }
+            }
+        }
+    }
+
+    /// Merge the vertices of the open boundaries whose positions coincide within `epsilon`.
+    ///
+    /// Boundary vertices are the ones of the edges used by a single face. Interior vertices are
+    /// never merged, even when they coincide. Each group is replaced by its first vertex and
+    /// the remaining vertices are renumbered, keeping their order. With `preserve_seams`, only
+    /// the vertices whose faces belong to the same objects and groups are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 4 5 6";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.weld_boundary_vertices(1e-6, false);
+    /// assert_eq!(data.vertices.len(), 4);
+    /// assert_eq!(data.faces[1], vec![(1,None,None), (3,None,None), (2,None,None)]);
+    /// ```
+    pub fn weld_boundary_vertices(&mut self, epsilon : f32, preserve_seams : bool) {
+        let mut boundary : Vec<usize> = self.build_edge_map().into_iter()
+            .filter(|(_,faces)| faces.len() == 1)
+            .flat_map(|((a,b),_)| vec![a,b])
+            .filter(|&v| v < self.vertices.len())
+            .collect();
+        boundary.sort();
+        boundary.dedup();
+        let mut groups = self.coincident_vertices(&boundary, epsilon);
+        if preserve_seams {
+            let regions = self.seam_regions();
+            let mut split = Vec::new();
+            for group in groups {
+                let mut parts : Vec<Vec<usize>> = Vec::new();
+                for v in group {
+                    match parts.iter().position(|p| regions[p[0]] == regions[v]) {
+                        Some(k) => parts[k].push(v),
+                        None => parts.push(vec![v]),
+                    }
+                }
+                split.extend(parts.into_iter().filter(|p| p.len() > 1));
+            }
+            groups = split;
+        }
+        self.merge_vertex_groups(&groups);
+    }
+
+    /// Remove from the faces the corners whose two adjacent edges are nearly collinear.
+    ///
+    /// A corner is removed when the angle between its incoming and outgoing edges,
+    /// in radians, is below `angle_epsilon`, so the shape of the face is unchanged.
+    /// Faces keep at least three corners and the vertices themselves are not removed.
+    /// A removed corner still used by a neighbour face creates a T-junction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 2 0 0\nv 0 1 0\nf 1 2 3 4".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.merge_collinear_vertices(1e-3);
+    /// assert_eq!(data.faces[0], vec![(0,None,None), (2,None,None), (3,None,None)]);
+    /// ```
+    pub fn merge_collinear_vertices(&mut self, angle_epsilon : f32) {
+        for i in 0..self.faces.len() {
+            let mut k = 0;
+            while self.faces[i].len() > 3 && k < self.faces[i].len() {
+                let n = self.faces[i].len();
+                let prev = self.position(self.faces[i][(k+n-1)%n].0);
+                let cur = self.position(self.faces[i][k].0);
+                let next = self.position(self.faces[i][(k+1)%n].0);
+                let e1 = sub(cur,prev);
+                let e2 = sub(next,cur);
+                let collinear = length(e1) == 0. || length(e2) == 0. || angle(e1,e2) < angle_epsilon;
+                if collinear {
+                    self.faces[i].remove(k);
+                    // The previous corner may have become collinear too.
+                    k = k.saturating_sub(1);
+                } else {
+                    k += 1;
+                }
+            }
+        }
+    }
+
+    /// Merge the pairs of triangles sharing an edge into quads, for the formats preferring them.
+    ///
+    /// Two triangles are merged when the angle between their normals, in radians, is at most
+    /// `angle_tol`, their union is a strictly convex quad with the same orientation, and they
+    /// have the same corners on the shared edge, object, groups, material and smoothing group.
+    /// The quad takes the place of the first triangle and the next faces are shifted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.merge_coplanar_triangles(1e-3);
+    /// assert_eq!(data.faces, vec![vec![(0,None,None), (1,None,None), (2,None,None), (3,None,None)]]);
+    /// ```
+    pub fn merge_coplanar_triangles(&mut self, angle_tol : f32) {
+        let mut groups : Vec<Vec<usize>> = vec![Vec::new(); self.faces.len()];
+        for (k,g) in self.groups.iter().enumerate() {
+            for &i in &g.indexes {
+                if i < groups.len() {
+                    groups[i].push(k);
+                }
+            }
+        }
+        let mut objects : Vec<Vec<usize>> = vec![Vec::new(); self.faces.len()];
+        for (k,o) in self.objects.iter().enumerate() {
+            for &i in &o.primitives {
+                if i < objects.len() {
+                    objects[i].push(k);
+                }
+            }
+        }
+        for l in groups.iter_mut().chain(objects.iter_mut()) {
+            l.sort_unstable();
+        }
+        let mut edges : Vec<((usize,usize),Vec<usize>)> = self.build_edge_map().into_iter().collect();
+        edges.sort_unstable();
+        let mut removed = vec![false; self.faces.len()];
+        let mut merged = vec![false; self.faces.len()];
+        for (_,faces) in edges {
+            if faces.len() != 2 {
+                continue;
+            }
+            let (f,g) = (faces[0],faces[1]);
+            if merged[f] || merged[g] || self.faces[f].len() != 3 || self.faces[g].len() != 3
+                || objects[f] != objects[g] || groups[f] != groups[g]
+                || self.face_material(f) != self.face_material(g)
+                || self.face_smoothing_group(f) != self.face_smoothing_group(g) {
+                continue;
+            }
+            let (nf,ng) = (self.face_normal(f),self.face_normal(g));
+            if length(nf) == 0. || length(ng) == 0. || angle(nf,ng) > angle_tol {
+                continue;
+            }
+            // The edge goes from `x` to `y` in `f` and back in `g`
+            let (tf,tg) = (&self.faces[f],&self.faces[g]);
+            let shared = (0..3).flat_map(|k| (0..3).map(move |j| (k,j)))
+                .find(|&(k,j)| tf[k] == tg[(j+1)%3] && tf[(k+1)%3] == tg[j]);
+            let (k,j) = match shared {
+                Some(s) => s,
+                None => continue,
+            };
+            // Rotated to start with the first corner of `f`
+            let start = [0,3,2][k];
+            let mut quad = vec![tf[k], tg[(j+2)%3], tf[(k+1)%3], tf[(k+2)%3]];
+            quad.rotate_left(start);
+            let n = add(nf,ng);
+            let convex = (0..4).all(|c| {
+                let a = self.position(quad[c].0);
+                let b = self.position(quad[(c+1)%4].0);
+                let d = self.position(quad[(c+2)%4].0);
+                dot(cross(sub(b,a),sub(d,b)),n) > 0.
+            });
+            if !convex {
+                continue;
+            }
+            if let (Some(sf),Some(sg)) = (self.face_slashes.get(f),self.face_slashes.get(g)) {
+                if sf.len() == 3 && sg.len() == 3 {
+                    let mut slashes = vec![sf[k], sg[(j+2)%3], sf[(k+1)%3], sf[(k+2)%3]];
+                    slashes.rotate_left(start);
+                    self.face_slashes[f] = slashes;
+                }
+            }
+            self.faces[f] = quad;
+            merged[f] = true;
+            merged[g] = true;
+            removed[g] = true;
+        }
+        self.remove_faces(&removed);
+    }
+
+    /// Separate the faces by moving each of them by `amount` along its normal.
+    ///
+    /// Every corner gets its own vertex, so the faces no longer share vertices and the
+    /// vertex list is rebuilt in face order. Normals and texture coordinates are unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a face, line or point has a vertex index past the end of `vertices`, since the
+    /// rebuilt list has no vertex for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.explode(0.5);
+    /// assert_eq!(data.vertices, vec![(0.,0.,0.5,1.), (1.,0.,0.5,1.), (0.,1.,0.5,1.)]);
+    /// ```
+    pub fn explode(&mut self, amount : f32) {
+        let mut vertices = Vec::new();
+        for i in 0..self.faces.len() {
+            let offset = scale(self.face_normal(i),amount);
+            for c in 0..self.faces[i].len() {
+                let v = self.faces[i][c].0;
+                let (x,y,z) = add(self.position(v),offset);
+                vertices.push((x,y,z,self.vertices[v].3));
+                self.faces[i][c].0 = vertices.len()-1;
+            }
+        }
+        // Lines and points are not moved
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                vertices.push(self.vertices[c.0]);
+                c.0 = vertices.len()-1;
+            }
+        }
+        for p in &mut self.points {
+            for v in p.iter_mut() {
+                vertices.push(self.vertices[*v]);
+                *v = vertices.len()-1;
+            }
+        }
+        self.vertices = vertices;
+    }
+
+    /// Give every face corner its own vertex, texture coordinates and normal, so that no
+    /// element is shared.
+    ///
+    /// The lists are rebuilt in face order, followed by the elements of the lines and points.
+    /// Objects, groups, materials and smoothing groups are unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a face, line or point has an index past the end of `vertices`, `texcoords` or
+    /// `normals`, since the rebuilt lists have no element for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\nf 2//1 4//1 3//1".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.explode_corners();
+    /// assert_eq!(data.vertices.len(), 6);
+    /// assert_eq!(data.normals.len(), 6);
+    /// assert_eq!(data.faces[1], vec![(3,None,Some(3)), (4,None,Some(4)), (5,None,Some(5))]);
+    /// ```
+    pub fn explode_corners(&mut self) {
+        let mut vertices = Vec::new();
+        let mut texcoords = Vec::new();
+        let mut normals = Vec::new();
+        for f in &mut self.faces {
+            for c in f.iter_mut() {
+                vertices.push(self.vertices[c.0]);
+                c.0 = vertices.len()-1;
+                if let Some(t) = c.1 {
+                    texcoords.push(self.texcoords[t]);
+                    c.1 = Some(texcoords.len()-1);
+                }
+                if let Some(n) = c.2 {
+                    normals.push(self.normals[n]);
+                    c.2 = Some(normals.len()-1);
+                }
+            }
+        }
+        for l in &mut self.lines {
+            for c in l.iter_mut() {
+                vertices.push(self.vertices[c.0]);
+                c.0 = vertices.len()-1;
+                if let Some(t) = c.1 {
+                    texcoords.push(self.texcoords[t]);
+                    c.1 = Some(texcoords.len()-1);
+                }
+            }
+        }
+        for p in &mut self.points {
+            for v in p.iter_mut() {
+                vertices.push(self.vertices[*v]);
+                *v = vertices.len()-1;
+            }
+        }
+        self.vertices = vertices;
+        self.texcoords = texcoords;
+        self.normals = normals;
+    }
+
+    /// Move each vertex toward the average of its neighbors by `factor`, `iterations` times.
+    ///
+    /// Neighbors are the vertices sharing an edge of a face. With `pin_boundary`, the vertices
+    /// of the edges used by a single face do not move. Vertices without neighbor and weights
+    /// are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 1 3 0\nv 1 1 3\nf 1 2 4\nf 2 3 4\nf 3 1 4".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.laplacian_smooth(1, 0.5, true);
+    /// assert_eq!(data.vertices[3], (1.,1.,1.5,1.));
+    /// assert_eq!(data.vertices[0], (0.,0.,0.,1.));
+    /// ```
+    pub fn laplacian_smooth(&mut self, iterations : usize, factor : f32, pin_boundary : bool) {
+        let n = self.vertices.len();
+        let mut neighbors = vec![Vec::new(); n];
+        for (a,b) in self.unique_edges() {
+            if a < n && b < n {
+                neighbors[a].push(b);
+                neighbors[b].push(a);
+            }
+        }
+        if pin_boundary {
+            for ((a,b),faces) in self.build_edge_map() {
+                if faces.len() == 1 && a < n && b < n {
+                    neighbors[a].clear();
+                    neighbors[b].clear();
+                }
+            }
+        }
+        for _ in 0..iterations {
+            let positions : Vec<Vec3> = (0..n).map(|v| self.position(v)).collect();
+            for (v,adjacent) in neighbors.iter().enumerate() {
+                if adjacent.is_empty() {
+                    continue;
+                }
+                let sum = adjacent.iter().fold((0.,0.,0.), |s,&u| add(s,positions[u]));
+                let average = scale(sum,1. / adjacent.len() as f32);
+                let (x,y,z) = add(positions[v],scale(sub(average,positions[v]),factor));
+                self.vertices[v] = (x,y,z,self.vertices[v].3);
+            }
+        }
+    }
+
+    /// Sort the objects by name, keeping the order of the objects with the same name.
+    ///
+    /// Faces are written object by object, so this makes the output of `write` independent
+    /// of the order in which the objects were loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{ObjData, Object};
+    ///
+    /// let mut data = ObjData::new();
+    /// data.objects.push(Object::new(String::from("b")));
+    /// data.objects.push(Object::new(String::from("a")));
+    /// data.sort_objects_by_name();
+    /// assert_eq!(data.objects[0].name, "a");
+    /// ```
+    pub fn sort_objects_by_name(&mut self) {
+        let mut order : Vec<usize> = (0..self.objects.len()).collect();
+        order.sort_by(|&a,&b| self.objects[a].name.cmp(&self.objects[b].name));
+        let mut remap = vec![0; order.len()];
+        for (new,&old) in order.iter().enumerate() {
+            remap[old] = new;
+        }
+        self.objects.sort_by(|a,b| a.name.cmp(&b.name));
+        for c in self.line_contexts.iter_mut().chain(self.point_contexts.iter_mut()) {
+            c.object = c.object.map(|o| remap.get(o).cloned().unwrap_or(o));
+        }
+    }
+
+    /// Remove the objects without faces, lines or points, such as the one declared by a
+    /// trailing `o` statement.
+    ///
+    /// Faces are left untouched, the objects referring to them and not the reverse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\no Mesh\nf 1 2 3\no Extra".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.remove_empty_objects();
+    /// assert_eq!(data.objects.len(), 1);
+    /// assert_eq!(data.objects[0].name, "Mesh");
+    /// ```
+    pub fn remove_empty_objects(&mut self) {
+        let mut used : Vec<bool> = self.objects.iter().map(|o| !o.primitives.is_empty()).collect();
+        for c in self.line_contexts.iter().chain(self.point_contexts.iter()) {
+            if let Some(u) = c.object.and_then(|o| used.get_mut(o)) {
+                *u = true;
+            }
+        }
+        let mut remap = Vec::with_capacity(used.len());
+        let mut next = 0;
+        for &u in &used {
+            remap.push(next);
+            if u {
+                next += 1;
+            }
+        }
+        let mut k = 0;
+        self.objects.retain(|_| {k += 1; used[k-1]});
+        for c in self.line_contexts.iter_mut().chain(self.point_contexts.iter_mut()) {
+            c.object = c.object.map(|o| remap.get(o).cloned().unwrap_or(o));
+        }
+    }
+
+    /// New `ObjData` holding the given faces and only the elements they use.
+    ///
+    /// Vertices, texture coordinates, normals and materials keep their relative order.
+    /// Objects and groups are restricted to the given faces, and dropped when left empty.
+    pub(crate) fn extract_faces(&self, faces : &[usize]) -> ObjData {
+        fn compact(used : Vec<bool>) -> Vec<Option<usize>> {
+            let mut next = 0;
+            used.into_iter().map(|u| if u {next += 1; Some(next-1)} else {None}).collect()
+        }
+        let mut used = (vec![false; self.vertices.len()], vec![false; self.texcoords.len()],
+                        vec![false; self.normals.len()], vec![false; self.materials.len()]);
+        for &i in faces {
+            for &(v,vt,vn) in &self.faces[i] {
+                used.0[v] = true;
+                if let Some(t) = vt {
+                    used.1[t] = true;
+                }
+                if let Some(n) = vn {
+                    used.2[n] = true;
+                }
+            }
+            if let Some(m) = self.face_material(i) {
+                used.3[m] = true;
+            }
+        }
+        let (vertices,texcoords,normals,materials) = (compact(used.0),compact(used.1),compact(used.2),compact(used.3));
+        let mut data = ObjData::new();
+        data.vertices = self.vertices.iter().zip(&vertices).filter(|&(_,r)| r.is_some()).map(|(v,_)| *v).collect();
+        data.texcoords = self.texcoords.iter().zip(&texcoords).filter(|&(_,r)| r.is_some()).map(|(t,_)| *t).collect();
+        data.normals = self.normals.iter().zip(&normals).filter(|&(_,r)| r.is_some()).map(|(n,_)| *n).collect();
+        data.materials = self.materials.iter().zip(&materials).filter(|&(_,r)| r.is_some()).map(|(m,_)| m.clone()).collect();
+        data.material_libs = self.material_libs.clone();
+        data.base_dir = self.base_dir.clone();
+        let mut remap = HashMap::new();
+        for (new,&i) in faces.iter().enumerate() {
+            remap.insert(i,new);
+            data.faces.push(self.faces[i].iter().map(|&(v,vt,vn)| {
+                (vertices[v].unwrap(), vt.and_then(|t| texcoords[t]), vn.and_then(|n| normals[n]))
+            }).collect());
+            data.face_materials.push(self.face_material(i).and_then(|m| materials[m]));
+            data.smoothing_groups.push(self.face_smoothing_group(i));
+            data.face_slashes.push(self.face_slashes.get(i).cloned().unwrap_or_default());
+        }
+        if self.face_lines.len() == self.faces.len() {
+            data.face_lines = faces.iter().map(|&i| self.face_lines[i]).collect();
+        }
+        for o in &self.objects {
+            let mut object = Object::new(o.name.clone());
+            object.primitives = o.primitives.iter().filter_map(|i| remap.get(i).cloned()).collect();
+            if !object.primitives.is_empty() {
+                data.objects.push(object);
+            }
+        }
+        for g in &self.groups {
+            let mut group = Group::new(g.name.clone());
+            group.indexes = g.indexes.iter().filter_map(|i| remap.get(i).cloned()).collect();
+            if !group.indexes.is_empty() {
+                data.groups.push(group);
+            }
+        }
+        data
+    }
+
+    /// Blend between this mesh at `t = 0` and `other` at `t = 1`, for morph targets.
+    ///
+    /// Vertices are interpolated linearly, and so are the normals, renormalized, when both
+    /// meshes have as many. Everything else is copied from `self`. The meshes are expected to
+    /// share their topology, `TopologyMismatch` being returned if their numbers of vertices
+    /// differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let mut input = BufReader::new("v 0 0 2\nv 3 0 2\nv 0 3 2\nf 1 2 3".as_bytes());
+    /// let target = ObjData::load(&mut input).ok().unwrap();
+    /// let blend = data.lerp(&target, 0.5).ok().unwrap();
+    /// assert_eq!(blend.vertices, vec![(0.,0.,1.,1.), (2.,0.,1.,1.), (0.,2.,1.,1.)]);
+    /// ```
+    pub fn lerp(&self, other : &ObjData, t : f32) -> Result<ObjData,LoadingError> {
+        if self.vertices.len() != other.vertices.len() {
+            return Err(LoadingError::TopologyMismatch(self.vertices.len(), other.vertices.len()));
+        }
+        let mix = |a : f32, b : f32| a + (b-a)*t;
+        let mut data = ObjData::new();
+        data.vertices = self.vertices.iter().zip(&other.vertices)
+            .map(|(a,b)| (mix(a.0,b.0), mix(a.1,b.1), mix(a.2,b.2), mix(a.3,b.3))).collect();
+        data.normals = if self.normals.len() == other.normals.len() {
+            self.normals.iter().zip(&other.normals).map(|(a,b)| {
+                let n = (mix(a.0,b.0), mix(a.1,b.1), mix(a.2,b.2));
+                let l = length(n);
+                if l > 0. {scale(n,1./l)} else {n}
+            }).collect()
+        } else {
+            self.normals.clone()
+        };
+        data.texcoords = self.texcoords.clone();
+        data.faces = self.faces.clone();
+        for o in &self.objects {
+            let mut object = Object::new(o.name.clone());
+            object.primitives = o.primitives.clone();
+            data.objects.push(object);
+        }
+        for g in &self.groups {
+            let mut group = Group::new(g.name.clone());
+            group.indexes = g.indexes.clone();
+            data.groups.push(group);
+        }
+        data.material_libs = self.material_libs.clone();
+        data.materials = self.materials.clone();
+        data.face_materials = self.face_materials.clone();
+        data.smoothing_groups = self.smoothing_groups.clone();
+        data.face_slashes = self.face_slashes.clone();
+        data.face_lines = self.face_lines.clone();
+        data.lines = self.lines.clone();
+        data.points = self.points.clone();
+        data.line_contexts = self.line_contexts.clone();
+        data.point_contexts = self.point_contexts.clone();
+        data.base_dir = self.base_dir.clone();
+        Ok(data)
+    }
+
+    /// Copy of the mesh for flat shading, where no vertex is shared between faces.
+    ///
+    /// Each corner gets its own vertex and each face a single normal, its own, so the number
+    /// of vertices is the number of corners and the number of normals the number of faces.
+    /// Texture coordinates, objects, groups, materials and smoothing groups are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let flat = data.to_flat_shaded();
+    /// assert_eq!(flat.vertices.len(), 36);
+    /// assert_eq!(flat.normals.len(), 12);
+    /// ```
+    pub fn to_flat_shaded(&self) -> ObjData {
+        let faces : Vec<usize> = (0..self.faces.len()).collect();
+        let mut flat = self.extract_faces(&faces);
+        flat.vertices.clear();
+        flat.normals.clear();
+        for (i,f) in flat.faces.iter_mut().enumerate() {
+            flat.normals.push(self.face_normal(i));
+            for (c,&(v,_,_)) in f.iter_mut().zip(self.faces[i].iter()) {
+                flat.vertices.push(self.vertices[v]);
+                c.0 = flat.vertices.len()-1;
+                c.2 = Some(i);
+            }
+        }
+        flat
+    }
+
+    /// Split the faces by material, in the order of their first face.
+    ///
+    /// Each part holds the faces of a material, with only the elements they use, and
+    /// the name of the material, `None` for the faces without material.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3\nusemtl red\nf 2 4 3";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let parts = data.split_by_material();
+    /// assert_eq!(parts[0].0, None);
+    /// assert_eq!(parts[1].0, Some(String::from("red")));
+    /// assert_eq!(parts[1].1.vertices.len(), 3);
+    /// ```
+    pub fn split_by_material(&self) -> Vec<(Option<String>,ObjData)> {
+        let mut parts : Vec<(Option<usize>,Vec<usize>)> = Vec::new();
+        for i in 0..self.faces.len() {
+            let material = self.face_material(i);
+            match parts.iter().position(|p| p.0 == material) {
+                Some(k) => parts[k].1.push(i),
+                None => parts.push((material,vec![i])),
+            }
+        }
+        parts.into_iter().map(|(m,faces)| (m.map(|m| self.materials[m].clone()), self.extract_faces(&faces))).collect()
+    }
+
+    /// Split the faces by object, in the order of `objects`.
+    ///
+    /// Each part holds the faces of an object, with only the elements they use, and the name
+    /// of the object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\no a\nf 1 2 3\no b\nf 2 4 3";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let parts = data.split_objects();
+    /// assert_eq!(parts[1].0, "b");
+    /// assert_eq!(parts[1].1.faces, vec![vec![(0,None,None), (2,None,None), (1,None,None)]]);
+    /// ```
+    pub fn split_objects(&self) -> Vec<(String,ObjData)> {
+        self.objects.iter().map(|o| (o.name.clone(), self.extract_faces(&o.primitives))).collect()
+    }
+
+    /// Reorder the faces, `order[i]` being the former index of the new face `i`.
+    ///
+    /// Objects, groups, materials, smoothing groups, slashes and source lines are updated accordingly,
+    /// and the lines and points declared after a face stay after it.
+    pub(crate) fn permute_faces(&mut self, order : &[usize]) {
+        let mut remap = vec![0; order.len()];
+        for (new,&old) in order.iter().enumerate() {
+            remap[old] = new;
+        }
+        let mut faces : Vec<_> = self.faces.drain(..).map(Some).collect();
+        self.faces = order.iter().map(|&old| faces[old].take().unwrap()).collect();
+        if !self.face_materials.is_empty() {
+            self.face_materials = order.iter().map(|&old| self.face_material(old)).collect();
+        }
+        if !self.smoothing_groups.is_empty() {
+            self.smoothing_groups = order.iter().map(|&old| self.face_smoothing_group(old)).collect();
+        }
+        if !self.face_slashes.is_empty() {
+            self.face_slashes = order.iter().map(|&old| self.face_slashes.get(old).cloned().unwrap_or_default()).collect();
+        }
+        if self.face_lines.len() == order.len() {
+            self.face_lines = order.iter().map(|&old| self.face_lines[old]).collect();
+        } else {
+            self.face_lines.clear();
+        }
+        for o in &mut self.objects {
+            for p in &mut o.primitives {
+                *p = remap[*p];
+            }
+        }
+        for g in &mut self.groups {
+            g.indexes = g.indexes.iter().map(|&i| remap[i]).collect();
+        }
+        // Keep the lines and points after the face they followed
+        for c in self.line_contexts.iter_mut().chain(self.point_contexts.iter_mut()) {
+            if c.faces_before > 0 && c.faces_before <= order.len() {
+                c.faces_before = remap[c.faces_before-1] + 1;
+            }
+        }
+    }
+
+    /// Remove the faces `i` for which `removed[i]` is set, the next ones being shifted.
+    ///
+    /// Objects, groups, materials, smoothing groups, slashes, source lines and the contexts of
+    /// the lines and points are updated accordingly.
+    pub(crate) fn remove_faces(&mut self, removed : &[bool]) {
+        let mut remap = Vec::with_capacity(removed.len());
+        let mut next = 0;
+        for &r in removed {
+            remap.push(if r {None} else {next += 1; Some(next-1)});
+        }
+        let kept = |i : &usize| !removed.get(*i).cloned().unwrap_or(false);
+        let mut i = 0;
+        self.faces.retain(|_| {i += 1; kept(&(i-1))});
+        let mut i = 0;
+        self.face_materials.retain(|_| {i += 1; kept(&(i-1))});
+        let mut i = 0;
+        self.smoothing_groups.retain(|_| {i += 1; kept(&(i-1))});
+        let mut i = 0;
+        self.face_slashes.retain(|_| {i += 1; kept(&(i-1))});
+        if self.face_lines.len() == removed.len() {
+            let mut i = 0;
+            self.face_lines.retain(|_| {i += 1; kept(&(i-1))});
+        } else {
+            self.face_lines.clear();
+        }
+        for o in &mut self.objects {
+            o.primitives = o.primitives.iter().filter_map(|&p| remap.get(p).cloned().unwrap_or(Some(p))).collect();
+        }
+        for g in &mut self.groups {
+            g.indexes = g.indexes.iter().filter_map(|&p| remap.get(p).cloned().unwrap_or(Some(p))).collect();
+        }
+        for c in self.line_contexts.iter_mut().chain(self.point_contexts.iter_mut()) {
+            c.faces_before -= removed.iter().take(c.faces_before).filter(|&&r| r).count();
+        }
+    }
+
+    /// Append `face` with the same object, groups, material and smoothing group as the face `like`.
+    pub(crate) fn push_face_like(&mut self, face : Face, like : usize) -> usize {
+        let i = self.faces.len();
+        self.faces.push(face);
+        let material = self.face_material(like);
+        if material.is_some() || !self.face_materials.is_empty() {
+            self.face_materials.resize(i, None);
+            self.face_materials.push(material);
+        }
+        let smoothing = self.face_smoothing_group(like);
+        if smoothing != 0 || !self.smoothing_groups.is_empty() {
+            self.smoothing_groups.resize(i, 0);
+            self.smoothing_groups.push(smoothing);
+        }
+        if self.face_lines.len() == i {
+            let line = self.face_lines[like];
+            self.face_lines.push(line);
+        }
+        for o in &mut self.objects {
+            if o.primitives.contains(&like) {
+                o.primitives.push(i);
+            }
+        }
+        for g in &mut self.groups {
+            if g.indexes.contains(&like) {
+                g.indexes.insert(i);
+            }
+        }
+        i
+    }
+
+    /// Corner in the middle of the corners `a` and `b`, creating the elements it needs once per edge.
+    fn midpoint_corner(&mut self, a : Corner, b : Corner, cache : &mut [HashMap<(usize,usize),usize>;3]) -> Corner {
+        let v = *cache[0].entry((a.0.min(b.0),a.0.max(b.0))).or_insert_with(|| {
+            let (p,q) = (self.vertices[a.0],self.vertices[b.0]);
+            self.vertices.push(((p.0+q.0)/2.,(p.1+q.1)/2.,(p.2+q.2)/2.,(p.3+q.3)/2.));
+            self.vertices.len()-1
+        });
+        let vt = match (a.1,b.1) {
+            (Some(s),Some(t)) => Some(*cache[1].entry((s.min(t),s.max(t))).or_insert_with(|| {
+                let (p,q) = (self.texcoords[s],self.texcoords[t]);
+                self.texcoords.push(scale(add(p,q),0.5));
+                self.texcoords.len()-1
+            })),
+            _ => None,
+        };
+        let vn = match (a.2,b.2) {
+            (Some(s),Some(t)) => Some(*cache[2].entry((s.min(t),s.max(t))).or_insert_with(|| {
+                let n = add(self.normals[s],self.normals[t]);
+                let l = length(n);
+                self.normals.push(if l > 0. {scale(n,1./l)} else {self.normals[s]});
+                self.normals.len()-1
+            })),
+            _ => None,
+        };
+        (v,vt,vn)
+    }
+
+    /// Split the faces whose area exceeds `max_area` until none does.
+    ///
+    /// A face too large is triangulated as a fan and each triangle is split in four at the
+    /// middle of its edges, interpolating the texture coordinates and normals. The new faces
+    /// are added at the end with the object, groups, material and smoothing group of the
+    /// face they come from. The faces are split at most 8 times, and the neighbors of a
+    /// split face are not modified, so T-junctions may appear. The faces with an index past the
+    /// end of `vertices`, `texcoords` or `normals` are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 0 2 0\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.subdivide_adaptive(1.);
+    /// assert_eq!(data.faces.len(), 4);
+    /// assert_eq!(data.vertices.len(), 6);
+    /// ```
+    pub fn subdivide_adaptive(&mut self, max_area : f32) {
+        let mut cache = [HashMap::new(),HashMap::new(),HashMap::new()];
+        // Checked before the lists grow, so that no index past the end becomes valid
+        let skipped : Vec<bool> = self.faces.iter().map(|f| f.iter().any(|&(v,vt,vn)| {
+            v >= self.vertices.len() || vt.map_or(false, |t| t >= self.texcoords.len()) || vn.map_or(false, |n| n >= self.normals.len())
+        })).collect();
+        for _ in 0..MAX_SUBDIVISIONS {
+            let large : Vec<usize> = (0..self.faces.len()).filter(|&i| {
+                !skipped.get(i).cloned().unwrap_or(false) && self.face_area(i) > max_area
+            }).collect();
+            if large.is_empty() {
+                break;
+            }
+            for i in large {
+                let f = self.faces[i].clone();
+                let mut triangles = Vec::new();
+                for k in 1..f.len()-1 {
+                    let (a,b,c) = (f[0],f[k],f[k+1]);
+                    let ab = self.midpoint_corner(a,b,&mut cache);
+                    let bc = self.midpoint_corner(b,c,&mut cache);
+                    let ca = self.midpoint_corner(c,a,&mut cache);
+                    triangles.extend(vec![vec![a,ab,ca], vec![ab,b,bc], vec![ca,bc,c], vec![ab,bc,ca]]);
+                }
+                let mut triangles = triangles.into_iter();
+                self.faces[i] = triangles.next().unwrap();
+                for t in triangles {
+                    self.push_face_like(t, i);
+                }
+            }
+        }
+    }
+
+    /// Simplified copy of the mesh, where the vertices falling in the same cell of a grid of
+    /// size `cell_size` are collapsed to their centroid.
+    ///
+    /// Consecutive corners of a face merged together are reduced to one, and the faces left
+    /// with less than three corners are dropped. Texture coordinates, normals, objects,
+    /// groups and materials of the remaining faces are kept. Vertices unused by the faces are
+    /// not part of the result. With `preserve_seams`, only the vertices whose faces belong to
+    /// the same objects and groups are collapsed together.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_heightmap(&[0.; 25], 5, 5, (1.,1.,1.));
+    /// let simplified = data.simplify_clustering(2., false);
+    /// assert_eq!(data.faces.len(), 16);
+    /// assert_eq!(simplified.faces.len(), 4);
+    /// ```
+    pub fn simplify_clustering(&self, cell_size : f32, preserve_seams : bool) -> ObjData {
+        let regions = if preserve_seams {self.seam_regions()} else {vec![0; self.vertices.len()]};
+        let mut cells : HashMap<(i64,i64,i64,usize),usize> = HashMap::new();
+        let mut clusters : Vec<((f32,f32,f32,f32),usize)> = Vec::new();
+        let cluster : Vec<usize> = self.vertices.iter().zip(&regions).map(|(&(x,y,z,w),&r)| {
+            let key = |x : f32| (x / cell_size).floor() as i64;
+            let c = *cells.entry((key(x),key(y),key(z),r)).or_insert_with(|| {
+                clusters.push(((0.,0.,0.,0.),0));
+                clusters.len()-1
+            });
+            let (sum,n) = clusters[c];
+            clusters[c] = ((sum.0+x,sum.1+y,sum.2+z,sum.3+w),n+1);
+            c
+        }).collect();
+
+        let mut kept = Vec::new();
+        let mut faces = Vec::new();
+        for (i,f) in self.faces.iter().enumerate() {
+            let mut corners : Vec<usize> = (0..f.len()).collect();
+            corners.dedup_by_key(|&mut k| cluster[f[k].0]);
+            while corners.len() > 1 && cluster[f[corners[0]].0] == cluster[f[*corners.last().unwrap()].0] {
+                corners.pop();
+            }
+            if corners.len() >= 3 {
+                kept.push(i);
+                faces.push(corners);
+            }
+        }
+
+        let mut data = self.extract_faces(&kept);
+        let mut index : Vec<Option<usize>> = vec![None; clusters.len()];
+        data.vertices.clear();
+        for (k,&i) in kept.iter().enumerate() {
+            data.faces[k] = faces[k].iter().map(|&c| {
+                let cl = cluster[self.faces[i][c].0];
+                let v = *index[cl].get_or_insert_with(|| {
+                    let (sum,n) = clusters[cl];
+                    let n = n as f32;
+                    data.vertices.push((sum.0/n,sum.1/n,sum.2/n,sum.3/n));
+                    data.vertices.len()-1
+                });
+                (v,data.faces[k][c].1,data.faces[k][c].2)
+            }).collect();
+        }
+        data
+    }
+
+    /// Reorder the faces to improve the hit rate of the post-transform vertex cache.
+    ///
+    /// The faces of each object are greedily reordered by simulating a LRU vertex cache:
+    /// the next face is the one having the most vertices in the cache, favoring the most
+    /// recently used ones. Faces stay within their object, and objects, groups, materials
+    /// and smoothing groups are updated accordingly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.optimize_face_order();
+    /// assert_eq!(data.faces.len(), 12);
+    /// ```
+    pub fn optimize_face_order(&mut self) {
+        let mut order = Vec::with_capacity(self.faces.len());
+        let mut placed = vec![false; self.faces.len()];
+        for o in &self.objects {
+            for i in self.cache_order(&o.primitives) {
+                if !placed[i] {
+                    placed[i] = true;
+                    order.push(i);
+                }
+            }
+        }
+        for (i,p) in placed.iter().enumerate() {
+            if !p {
+                order.push(i);
+            }
+        }
+        self.permute_faces(&order);
+        // Objects list their faces in the optimized order.
+        for o in &mut self.objects {
+            o.primitives.sort();
+        }
+    }
+
+    /// Order the faces `faces` to make the best use of a vertex cache.
+    fn cache_order(&self, faces : &[usize]) -> Vec<usize> {
+        let mut vertex_faces : HashMap<usize,Vec<usize>> = HashMap::new();
+        for (k,&i) in faces.iter().enumerate() {
+            for &(v,_,_) in &self.faces[i] {
+                vertex_faces.entry(v).or_default().push(k);
+            }
+        }
+        let mut emitted = vec![false; faces.len()];
+        let mut cache : Vec<usize> = Vec::with_capacity(VERTEX_CACHE_SIZE);
+        let mut order = Vec::with_capacity(faces.len());
+        let mut next = 0;
+        while order.len() < faces.len() {
+            // Score the faces using the cached vertices, the most recent ones weighing more.
+            let mut best : Option<(usize,usize)> = None;
+            for v in &cache {
+                for &k in &vertex_faces[v] {
+                    if emitted[k] {
+                        continue;
+                    }
+                    let score = self.faces[faces[k]].iter()
+                        .filter_map(|&(u,_,_)| cache.iter().position(|&c| c == u))
+                        .map(|p| VERTEX_CACHE_SIZE - p)
+                        .sum();
+                    let better = match best {
+                        Some((s,b)) => score > s || (score == s && k < b),
+                        None => true,
+                    };
+                    if better {
+                        best = Some((score,k));
+                    }
+                }
+            }
+            let k = match best {
+                Some((_,k)) => k,
+                None => {
+                    while emitted[next] {
+                        next += 1;
+                    }
+                    next
+                },
+            };
+            emitted[k] = true;
+            order.push(faces[k]);
+            for &(v,_,_) in self.faces[faces[k]].iter().rev() {
+                cache.retain(|&c| c != v);
+                cache.insert(0,v);
+            }
+            cache.truncate(VERTEX_CACHE_SIZE);
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::io::BufWriter;
+    use std::str;
+    use obj::*;
+    use super::*;
+
+    #[test]
+    fn optimize_vertex_order() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut optimized = ObjData::load(&mut input).ok().unwrap();
+        optimized.optimize_vertex_order();
+
+        assert_eq!(data.vertices.len(),optimized.vertices.len());
+        assert_eq!(vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],data.faces[0]);
+        assert_eq!(vec![(0,None,Some(0)), (1,None,Some(0)), (2,None,Some(0))],optimized.faces[0]);
+        assert_eq!(vec![(3,None,Some(1)), (4,None,Some(1)), (5,None,Some(1))],optimized.faces[1]);
+        for (f,o) in data.faces.iter().zip(optimized.faces.iter()) {
+            for (&(v,vt,vn),&(ov,ovt,ovn)) in f.iter().zip(o.iter()) {
+                assert_eq!(data.vertices[v],optimized.vertices[ov]);
+                assert_eq!(vt,ovt);
+                assert_eq!(vn,ovn);
+            }
+        }
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 2 0 0
+        v 3 0 0
+        f 4 2 1
+        l 3 4"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.optimize_vertex_order();
+        assert_eq!(vec![(3.,0.,0.,1.), (1.,0.,0.,1.), (0.,0.,0.,1.), (2.,0.,0.,1.)],data.vertices);
+        assert_eq!(vec![(0,None,None), (1,None,None), (2,None,None)],data.faces[0]);
+        assert_eq!(vec![vec![(3,None), (0,None)]],data.lines);
+
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nf 2 3 1\np 5 2".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.optimize_vertex_order();
+        assert_eq!(vec![(1.,0.,0.,1.), (0.,0.,0.,1.)],data.vertices);
+        assert_eq!(vec![(0,None,None), (2,None,None), (1,None,None)],data.faces[0]);
+        assert_eq!(vec![vec![4,0]],data.points);
+    }
+
+    #[test]
+    fn scale_uniform() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        let normals = data.normals.clone();
+        data.scale_uniform(1000.);
+        assert_eq!((1000.,-1000.,-1000.,1.),data.vertices[0]);
+        assert_eq!((-1000.,1000.,-1000.,1.),data.vertices[7]);
+        assert_eq!(normals,data.normals);
+
+        data.to_meters_from_millimeters();
+        assert_eq!((1.,-1.,-1.,1.),data.vertices[0]);
+        data.to_centimeters_from_meters();
+        assert_eq!((100.,-100.,-100.,1.),data.vertices[0]);
+        data.to_meters_from_centimeters();
+        assert_eq!((1.,-1.,-1.,1.),data.vertices[0]);
+    }
+
+    #[test]
+    fn dehomogenize() {
+        let obj_str =
+        r#"v 2 4 6 2
+        v 1 2 3
+        v 1 -1 0.5 0
+        v 1 1 1 -0.5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.dehomogenize();
+        assert_eq!(vec![(1.,2.,3.,1.), (1.,2.,3.,1.), (1.,-1.,0.5,0.), (-2.,-2.,-2.,1.)],data.vertices);
+    }
+
+    #[test]
+    fn laplacian_smooth() {
+        let variance = |data : &ObjData| {
+            let n = data.vertices.len() as f32;
+            let mean = data.vertices.iter().map(|v| v.2).sum::<f32>() / n;
+            data.vertices.iter().map(|v| (v.2 - mean)*(v.2 - mean)).sum::<f32>() / n
+        };
+        // A plane z = 0 with pseudo-random noise
+        let mut state = 1u32;
+        let heights : Vec<f32> = (0..100).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as f32 / 65_536. - 0.5
+        }).collect();
+        let noisy = ObjData::from_heightmap(&heights,10,10,(1.,1.,1.));
+        let mut data = ObjData::from_heightmap(&heights,10,10,(1.,1.,1.));
+        data.map_vertices(|(x,y,z,w)| (x,z,y,w));
+        let before = variance(&data);
+        data.laplacian_smooth(5, 0.5, false);
+        let after = variance(&data);
+        assert!(after < before / 2.);
+
+        let mut pinned = ObjData::from_heightmap(&heights,10,10,(1.,1.,1.));
+        pinned.laplacian_smooth(5, 0.5, true);
+        for v in 0..100 {
+            let (x,y) = (v % 10, v / 10);
+            let boundary = x == 0 || y == 0 || x == 9 || y == 9;
+            assert_eq!(boundary,pinned.vertices[v] == noisy.vertices[v]);
+        }
+    }
+
+    #[test]
+    fn map_vertices() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.map_vertices(|(x,y,z,w)| (2.*x,y,z,w));
+        assert_eq!(Some(((-2.,-1.,-1.),(2.,1.,1.))),data.bounds(0..data.vertices.len()));
+
+        data.map_normals(|(x,y,z)| (-x,-y,-z));
+        assert_eq!((0.,1.,0.),data.normals[0]);
+        data.texcoords.push((0.25,0.5,0.));
+        data.map_texcoords(|(u,v,w)| (u,1.-v,w));
+        assert_eq!(vec![(0.25,0.5,0.)],data.texcoords);
+    }
+
+    #[test]
+    fn quantize_positions() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        for (i,v) in data.vertices.iter_mut().enumerate() {
+            v.0 += 0.01 * i as f32;
+            v.1 *= 0.37 * i as f32;
+        }
+        let original = data.vertices.clone();
+        let (min,max) = data.quantize_positions(8);
+        assert_eq!((-0.98,-1.,1.05,1.),(min.0,min.2,max.0,max.2));
+        let steps = 255.;
+        for (o,q) in original.iter().zip(data.vertices.iter()) {
+            assert!((o.0-q.0).abs() <= (max.0-min.0) / steps / 2. + 1e-6);
+            assert!((o.1-q.1).abs() <= (max.1-min.1) / steps / 2. + 1e-6);
+            assert!((o.2-q.2).abs() <= 1e-6);
+            let k = (q.1 - min.1) / (max.1 - min.1) * steps;
+            assert!((k - k.round()).abs() < 1e-3);
+            assert_eq!(o.3,q.3);
+        }
+
+        let mut empty = ObjData::new();
+        assert_eq!(((0.,0.,0.),(0.,0.,0.)),empty.quantize_positions(8));
+    }
+
+    #[test]
+    fn convert_axis() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        vn 0 0 1
+        f 1//1 2//1 3//1 4//1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.convert_axis(Axis::Zup, Axis::Zup);
+        assert_eq!((0.,0.,1.,1.),data.vertices[4]);
+        data.convert_axis(Axis::Zup, Axis::Yup);
+        assert_eq!((0.,1.,0.,1.),data.vertices[4]);
+        assert_eq!((1.,0.,-1.,1.),data.vertices[2]);
+        assert_eq!((0.,1.,0.),data.normals[0]);
+        data.convert_axis(Axis::Yup, Axis::Zup);
+        assert_eq!((1.,1.,0.,1.),data.vertices[2]);
+        assert_eq!((0.,0.,1.),data.normals[0]);
+    }
+
+    #[test]
+    fn normalize() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        for v in &mut data.vertices {
+            v.0 = v.0 * 3. + 10.;
+            v.1 = v.1 * 0.5 - 2.;
+            v.2 *= 1.7;
+        }
+        let original = data.vertices.clone();
+        let t = data.normalize();
+        assert_eq!(1./3.,t.scale);
+        assert!((t.translation.0 + 10./3.).abs() < 1e-5);
+        assert!((t.translation.1 - 2./3.).abs() < 1e-5);
+        assert!(t.translation.2.abs() < 1e-5);
+        assert!((data.vertices[0].0 - 1.).abs() < 1e-5);
+        assert!((data.vertices[0].1 + 1./6.).abs() < 1e-5);
+        data.apply_inverse(&t);
+        for (o,v) in original.iter().zip(data.vertices.iter()) {
+            assert!((o.0-v.0).abs() < 1e-5);
+            assert!((o.1-v.1).abs() < 1e-5);
+            assert!((o.2-v.2).abs() < 1e-5);
+            assert_eq!(o.3,v.3);
+        }
+
+        let mut point = ObjData::new();
+        point.vertices.push((1.,2.,3.,1.));
+        let t = point.normalize();
+        assert_eq!((0.,0.,0.,1.),point.vertices[0]);
+        point.apply_inverse(&t);
+        assert_eq!((1.,2.,3.,1.),point.vertices[0]);
+    }
+
+    #[test]
+    fn recenter() {
+        let obj_str =
+        r#"v 1 2 3
+        v 5 4 3
+        v 3 3 7 0.5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+
+        data.recenter(Anchor::BottomCenter);
+        let (min,max) = data.bounds(0..data.vertices.len()).unwrap();
+        assert_eq!(0.,min.1);
+        assert_eq!((0.,0.),(min.0+max.0,min.2+max.2));
+        assert_eq!((0.,1.,2.,0.5),data.vertices[2]);
+
+        data.recenter(Anchor::Center);
+        let (min,max) = data.bounds(0..data.vertices.len()).unwrap();
+        assert_eq!((0.,0.,0.),add(min,max));
+        assert_eq!(vec![(-2.,-1.,-2.,1.), (2.,1.,-2.,1.), (0.,0.,2.,0.5)],data.vertices);
+
+        data.recenter(Anchor::Origin);
+        assert_eq!((0.,0.,2.,0.5),data.vertices[2]);
+        data.recenter(Anchor::Min);
+        assert_eq!((0.,0.,0.),data.bounds(0..data.vertices.len()).unwrap().0);
+        data.recenter(Anchor::Max);
+        assert_eq!((0.,0.,0.),data.bounds(0..data.vertices.len()).unwrap().1);
+
+        let mut empty = ObjData::new();
+        empty.recenter(Anchor::Center);
+        assert!(empty.vertices.is_empty());
+    }
+
+    #[test]
+    fn swap_texcoord_components() {
+        let obj_str =
+        r#"vt 0.25 0.5
+        vt 1 0 0.75"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.swap_texcoord_components(0,1);
+        assert_eq!(vec![(0.5,0.25,0.), (0.,1.,0.75)],data.texcoords);
+        data.swap_texcoord_components(2,2);
+        assert_eq!(vec![(0.5,0.25,0.), (0.,1.,0.75)],data.texcoords);
+        data.swap_texcoord_components(2,0);
+        assert_eq!(vec![(0.,0.25,0.5), (0.75,1.,0.)],data.texcoords);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_texcoord_components_out_of_bounds() {
+        let mut data = ObjData::new();
+        data.swap_texcoord_components(0,3);
+    }
+
+    #[test]
+    fn generate_planar_uv() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.generate_planar_uv(Axis::Zup);
+        assert_eq!(data.vertices.len(),data.texcoords.len());
+        for &(u,v,w) in &data.texcoords {
+            assert!((0. ..=1.).contains(&u));
+            assert!((0. ..=1.).contains(&v));
+            assert_eq!(0.,w);
+        }
+        assert_eq!((1.,0.,0.),data.texcoords[0]);
+        for f in &data.faces {
+            for &(v,vt,vn) in f {
+                assert_eq!(Some(v),vt);
+                assert!(vn.is_some());
+            }
+        }
+
+        let mut flat = ObjData::new();
+        flat.vertices.push((1.,2.,3.,1.));
+        flat.generate_planar_uv(Axis::Yup);
+        assert_eq!(vec![(0.,0.,0.)],flat.texcoords);
+    }
+
+    #[test]
+    fn generate_planar_uvs() {
+        let obj_str =
+        r#"v -1 0 -1
+        v 3 0 -1
+        v 3 0 1
+        v -1 0 1
+        vt 0.5 0.5
+        f 1 2 3 4
+        f 1 3/1 4"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.generate_planar_uvs(Plane::XZ, false);
+        assert_eq!(5,data.texcoords.len());
+        for &(u,v,w) in &data.texcoords {
+            assert!((0. ..=1.).contains(&u));
+            assert!((0. ..=1.).contains(&v));
+            assert_eq!(0.,w);
+        }
+        assert!(data.faces.iter().flatten().all(|c| c.1.is_some()));
+        assert_eq!(Some(0),data.faces[1][1].1);
+        assert_eq!((1.,1.,0.),data.texcoords[data.faces[0][2].1.unwrap()]);
+        assert_eq!(data.faces[0][0].1,data.faces[1][0].1);
+
+        data.generate_planar_uvs(Plane::XZ, true);
+        assert_eq!(9,data.texcoords.len());
+        assert_eq!((0.5,0.5,0.),data.texcoords[0]);
+        assert_eq!(Some(7),data.faces[1][1].1);
+        assert_eq!((1.,1.,0.),data.texcoords[7]);
+
+        data.generate_planar_uvs(Plane::XY, true);
+        assert_eq!((1.,0.,0.),data.texcoords[data.faces[0][2].1.unwrap()]);
+        data.generate_planar_uvs(Plane::YZ, true);
+        assert_eq!((0.,1.,0.),data.texcoords[data.faces[0][2].1.unwrap()]);
+    }
+
+    #[test]
+    fn generate_box_uv() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.generate_box_uv();
+        assert_eq!(24,data.texcoords.len());
+        for f in &data.faces {
+            let corners : Vec<(f32,f32,f32,f32)> = f.iter().map(|c| data.vertices[c.0]).collect();
+            let uvs : Vec<(f32,f32,f32)> = f.iter().map(|c| data.texcoords[c.1.unwrap()]).collect();
+            for (p,uv) in corners.iter().zip(uvs.iter()) {
+                // Cube faces are axis aligned, the uv are the two other coordinates mapped from [-1,1]
+                let coords = [(p.0+1.)/2., (p.1+1.)/2., (p.2+1.)/2.];
+                let fixed = if corners.iter().all(|c| c.0 == p.0) {0}
+                    else if corners.iter().all(|c| c.1 == p.1) {1}
+                    else {2};
+                let others : Vec<f32> = (0..3).filter(|&k| k != fixed).map(|k| coords[k]).collect();
+                assert_eq!((others[0],others[1],0.),*uv);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_vertex_normals() {
+        // Two triangles around the first vertex, with angles of 90 and 45 degrees
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 0 1 1
+        vn 0 1 0
+        f 1//1 2//1 3//1
+        f 1 3 4"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.generate_vertex_normals(false);
+        assert_eq!(4,data.normals.len());
+        let s = 0.5f32.sqrt();
+        let n = data.normals[0];
+        assert!((n.0 - s).abs() < 1e-6 && n.1.abs() < 1e-6 && (n.2 - s).abs() < 1e-6);
+        assert_eq!((0.,0.,1.),data.normals[1]);
+        assert_eq!((1.,0.,0.),data.normals[3]);
+        assert_eq!(vec![(0,None,Some(0)),(2,None,Some(2)),(3,None,Some(3))],data.faces[1]);
+
+        data.generate_vertex_normals(true);
+        let expected = (1./5f32.sqrt(),0.,2./5f32.sqrt());
+        let n = data.normals[0];
+        assert!((n.0 - expected.0).abs() < 1e-6 && n.1.abs() < 1e-6 && (n.2 - expected.2).abs() < 1e-6);
+        // The angles are swapped at the third vertex
+        let n = data.normals[2];
+        assert!((n.0 - expected.2).abs() < 1e-6 && n.1.abs() < 1e-6 && (n.2 - expected.0).abs() < 1e-6);
+
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 3 4".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.generate_vertex_normals(true);
+        assert_eq!(vec![(0.,0.,1.); 3],data.normals);
+        assert_eq!(vec![(0,None,Some(0)),(2,None,Some(2)),(3,None,None)],data.faces[1]);
+    }
+
+    #[test]
+    fn positions_only() {
+        let obj_str =
+        r#"o Quad
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vn 0 0 1
+        f 1/1/1 2/2/1 3/3/1
+        f 1//1 3//1 4//1
+        f 1/1 3/3 4/2"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.positions_only();
+        assert_eq!(4,data.vertices.len());
+        assert!(data.texcoords.is_empty() && data.normals.is_empty());
+        assert_eq!(vec![(0,None,None), (2,None,None), (3,None,None)],data.faces[2]);
+
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        assert!(written.lines().all(|l| !l.starts_with("vt") && !l.starts_with("vn")));
+        let faces : Vec<&str> = written.lines().filter(|l| l.starts_with("f ")).collect();
+        assert_eq!(vec!["f 1 2 3","f 1 3 4","f 1 3 4"],faces);
+    }
+
+    #[test]
+    fn flip_winding() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut flipped = ObjData::load(&mut input).ok().unwrap();
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+        f 1/1/1 2/2/1 3/3/1 4/4/1
+        f 1/4 3/2 4/1"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut textured = ObjData::load(&mut input).ok().unwrap();
+        textured.flip_winding_preserve_uv();
+        assert_eq!(vec![(3,Some(3),Some(0)), (2,Some(2),Some(0)), (1,Some(1),Some(0)), (0,Some(0),Some(0))],textured.faces[0]);
+        assert_eq!(vec![(3,Some(0),None), (2,Some(1),None), (0,Some(3),None)],textured.faces[1]);
+
+        flipped.flip_winding();
+        for (f,o) in data.faces.iter().zip(flipped.faces.iter()) {
+            let mut reversed = f.clone();
+            reversed.reverse();
+            assert_eq!(&reversed,o);
+        }
+        flipped.flip_winding();
+        assert_eq!(data.faces,flipped.faces);
+    }
+
+    #[test]
+    fn orient_outward() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut oriented = ObjData::load(&mut input).ok().unwrap();
+
+        for &i in [0,3,4,11].iter() {
+            oriented.faces[i].reverse();
+        }
+        assert!(!oriented.check_winding().is_empty());
+        oriented.orient_outward();
+        assert_eq!(data.faces,oriented.faces);
+        for i in 0..oriented.faces.len() {
+            let (min,max) = oriented.face_bounding_box(i).unwrap();
+            assert!(dot(oriented.face_normal(i),add(min,max)) > 0.);
+        }
+    }
+
+    #[test]
+    fn fix_normal_orientation() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut inverted = ObjData::load(&mut input).ok().unwrap();
+        inverted.normals[1] = (0.,-1.,0.);
+        inverted.fix_normal_orientation();
+        assert_eq!(data.normals,inverted.normals);
+        assert_eq!(data.faces,inverted.faces);
+
+        // A normal shared by two faces of opposite orientation
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 0
+        vn 0 0 1
+        f 1//1 2//1 3//1
+        f 2//1 4//1 3//1
+        f 3//1 4//1 2//1"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.fix_normal_orientation();
+        assert_eq!(vec![(0.,0.,1.),(0.,0.,-1.)],data.normals);
+        assert_eq!(vec![(2,None,Some(1)),(3,None,Some(1)),(1,None,Some(1))],data.faces[2]);
+        assert_eq!(Some(0),data.faces[1][0].2);
+    }
+
+    #[test]
+    fn merge_positions_keep_attributes() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 0
+        v 1 1 0
+        v 5 5 5
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+        vn 0 0 -1
+        f 1/1/1 2/2/1 3/3/1
+        f 5/1/2 6/3/2 4/4/2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.merge_positions_keep_attributes(1e-6);
+        assert_eq!(vec![(0.,0.,0.,1.), (1.,0.,0.,1.), (1.,1.,0.,1.), (0.,1.,0.,1.), (5.,5.,5.,1.)],data.vertices);
+        assert_eq!(4,data.texcoords.len());
+        assert_eq!(2,data.normals.len());
+        assert_eq!(vec![(0,Some(0),Some(0)), (1,Some(1),Some(0)), (2,Some(2),Some(0))],data.faces[0]);
+        assert_eq!(vec![(0,Some(0),Some(1)), (2,Some(2),Some(1)), (3,Some(3),Some(1))],data.faces[1]);
+    }
+
+    #[test]
+    fn deduplicate_attributes() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 0 0
+        vt 1 1
+        vt 1 0.0000001
+        vn 0 0 1
+        vn 0 0 -1
+        vn 0 0 1
+        vn 0 0 1
+        f 1/1/1 2/2/3 3/4/4
+        f 1/3/2 3/5/2 4/4/2
+        l 1/3 2/5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.deduplicate_attributes();
+        assert_eq!(vec![(0.,0.,1.),(0.,0.,-1.)],data.normals);
+        assert_eq!(vec![(0.,0.,0.),(1.,0.,0.),(1.,1.,0.)],data.texcoords);
+        assert_eq!(vec![(0,Some(0),Some(0)), (1,Some(1),Some(0)), (2,Some(2),Some(0))],data.faces[0]);
+        assert_eq!(vec![(0,Some(0),Some(1)), (2,Some(1),Some(1)), (3,Some(2),Some(1))],data.faces[1]);
+        assert_eq!(vec![vec![(0,Some(0)), (1,Some(1))]],data.lines);
+    }
+
+    #[test]
+    fn subdivide_adaptive() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 10 0 0
+        v 0 10 0
+        vt 0 0
+        vt 1 0
+        vt 0 1
+        usemtl red
+        g small
+        f 1 2 3
+        g large
+        f 1/1 4/2 5/3
+        g small
+        f 2 3 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.subdivide_adaptive(10.);
+        // The large triangle of area 50 is split twice in four, down to 16 triangles of area 3.125
+        assert_eq!(18,data.faces.len());
+        assert_eq!(vec![(0,None,None), (1,None,None), (2,None,None)],data.faces[0]);
+        assert_eq!(vec![(1,None,None), (2,None,None), (0,None,None)],data.faces[2]);
+        for i in 0..data.faces.len() {
+            assert!(data.face_area(i) <= 10.);
+            assert_eq!(Some(0),data.face_material(i));
+        }
+        assert_eq!(2,data.group("small").unwrap().indexes.len());
+        assert_eq!(16,data.group("large").unwrap().indexes.len());
+        assert_eq!(18,data.objects[0].primitives.len());
+        // 15 vertices in the large triangle, two of them already there
+        assert_eq!(5 + 15 - 3,data.vertices.len());
+        assert_eq!(15,data.texcoords.len());
+        let total : f32 = (0..data.faces.len()).map(|i| data.face_area(i)).sum();
+        assert!((total - 51.).abs() < 1e-4);
+        for i in 0..data.faces.len() {
+            assert_eq!((0.,0.,1.),data.face_normal(i));
+        }
+
+        let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 0 2 0\nvt 0 0\nf 1 2 4\nf 1/2 2/1 3/1\nf 1 2 3".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.subdivide_adaptive(1.);
+        assert_eq!(6,data.faces.len());
+        assert_eq!(vec![(0,None,None), (1,None,None), (3,None,None)],data.faces[0]);
+        assert_eq!(vec![(0,Some(1),None), (1,Some(0),None), (2,Some(0),None)],data.faces[1]);
+        assert_eq!(6,data.vertices.len());
+    }
+
+    #[test]
+    fn weld_boundary_vertices() {
+        // A fan around an interior vertex next to a quad, and an unused vertex
+        // coinciding with the interior one
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0.5 0.5 0
+        v 1 0 0
+        v 2 0 0
+        v 2 1 0
+        v 1 1 0
+        v 0.5 0.5 0
+        f 1 2 5
+        f 2 3 5
+        f 3 4 5
+        f 4 1 5
+        f 6 7 8 9"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        let before = data.build_edge_map().values().filter(|f| f.len() == 1).count();
+        assert_eq!(8,before);
+        data.weld_boundary_vertices(1e-5, false);
+        assert_eq!(8,data.vertices.len());
+        assert_eq!(vec![(1,None,None), (5,None,None), (6,None,None), (2,None,None)],data.faces[4]);
+        assert_eq!(6,data.build_edge_map().values().filter(|f| f.len() == 1).count());
+        assert_eq!(1,data.build_edge_map().values().filter(|f| f.len() == 2 && f.contains(&4)).count());
+        assert_eq!((0.5,0.5,0.,1.),data.vertices[4]);
+        assert_eq!((0.5,0.5,0.,1.),data.vertices[7]);
+
+        // Two groups sharing an edge, each with its own copy of the vertices
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        g left
+        f 1 2 3
+        g right
+        f 4 5 6"#;
+        for &(preserve_seams,count) in [(false,4),(true,6)].iter() {
+            let mut input = BufReader::new(obj_str.as_bytes());
+            let mut data = ObjData::load(&mut input).ok().unwrap();
+            data.weld_boundary_vertices(1e-5, preserve_seams);
+            assert_eq!(count,data.vertices.len());
+        }
+    }
+
+    #[test]
+    fn merge_collinear_vertices() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 2 0 0
+        v 2 1 0
+        v 2 2 0
+        v 0 2 0
+        v 1 1.01 0
+        f 1/1 2/2 3/3 4/4 5/5 6/6
+        f 1 2 7"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.merge_collinear_vertices(1e-3);
+        assert_eq!(vec![(0,Some(0),None), (2,Some(2),None), (4,Some(4),None), (5,Some(5),None)],data.faces[0]);
+        assert_eq!(vec![(0,None,None), (1,None,None), (6,None,None)],data.faces[1]);
+        assert_eq!(7,data.vertices.len());
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 2 0 0
+        v 3 0 0
+        v 0 1 0
+        f 1 2 3 4 5"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.merge_collinear_vertices(1e-3);
+        assert_eq!(vec![(0,None,None), (3,None,None), (4,None,None)],data.faces[0]);
+    }
+
+    #[test]
+    fn explode_corners() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut exploded = ObjData::load(&mut input).ok().unwrap();
+        exploded.explode_corners();
+
+        assert_eq!(36,exploded.vertices.len());
+        assert_eq!(36,exploded.normals.len());
+        assert!(exploded.texcoords.is_empty());
+        let corners : Vec<Corner> = exploded.faces.iter().flatten().cloned().collect();
+        assert_eq!((0..36).map(|k| (k,None,Some(k))).collect::<Vec<_>>(),corners);
+        for (f,g) in data.faces.iter().zip(&exploded.faces) {
+            for (&(v,_,n),&(w,_,m)) in f.iter().zip(g) {
+                assert_eq!(data.vertices[v],exploded.vertices[w]);
+                assert_eq!(data.normals[n.unwrap()],exploded.normals[m.unwrap()]);
+            }
+        }
+        assert_eq!(data.objects,exploded.objects);
+        assert_eq!(data.groups,exploded.groups);
+    }
+
+    #[test]
+    #[should_panic]
+    fn explode_corners_past_the_end() {
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//2".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.explode_corners();
+    }
+
+    #[test]
+    fn merge_coplanar_triangles() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 2 0 1
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+        o plane
+        g grid
+        f 1/1/1 2/2/1 3/3/1
+        f 1/1/1 3/3/1 4/4/1
+        g side
+        f 2 5 3
+        p 5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.merge_coplanar_triangles(1e-3);
+        assert_eq!(vec![vec![(0,Some(0),Some(0)), (1,Some(1),Some(0)), (2,Some(2),Some(0)), (3,Some(3),Some(0))],
+                        vec![(1,None,None), (4,None,None), (2,None,None)]],data.faces);
+        assert_eq!(vec![0,1],data.objects[0].primitives);
+        assert!(data.groups[0].indexes.contains(&0) && data.groups[0].indexes.len() == 1);
+        assert!(data.groups[1].indexes.contains(&1) && data.groups[1].indexes.len() == 1);
+        assert_eq!(Some(2),data.corner_slashes(0,3));
+        assert_eq!(vec![13,16],data.face_lines);
+        assert_eq!(2,data.point_contexts[0].faces_before);
+
+        // A concave union is left as two triangles
+        let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 1 1 0\nv 1 3 0\nf 1 2 3\nf 1 3 4".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.merge_coplanar_triangles(1e-3);
+        assert_eq!(2,data.faces.len());
+
+        // So are triangles folded along their shared edge
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 1\nf 1 2 3\nf 1 3 4".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.merge_coplanar_triangles(0.1);
+        assert_eq!(2,data.faces.len());
+        data.merge_coplanar_triangles(1.);
+        assert_eq!(1,data.faces.len());
+    }
+
+    #[test]
+    fn explode() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        let normals = data.normals.clone();
+        data.explode(0.5);
+
+        assert_eq!(36,data.vertices.len());
+        let mut used = Vec::new();
+        for f in &data.faces {
+            for &(v,_,_) in f {
+                assert!(!used.contains(&v));
+                used.push(v);
+            }
+        }
+        let max = data.vertices.iter().fold(0f32, |m,&(x,y,z,_)| m.max(x.abs()).max(y.abs()).max(z.abs()));
+        assert_eq!(1.5,max);
+        // The first face lies on the bottom side and moves down.
+        assert_eq!((1.,-1.5,1.,1.),data.vertices[0]);
+        assert_eq!(vec![(0,None,Some(0)), (1,None,Some(0)), (2,None,Some(0))],data.faces[0]);
+        assert_eq!(normals,data.normals);
+    }
+
+    #[test]
+    #[should_panic]
+    fn explode_past_the_end() {
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nf 1 2 3".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.explode(0.5);
+    }
+
+    #[test]
+    fn sort_objects_by_name() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        o Test
+        f 1 2 3
+        o Cube
+        f 2 3 1
+        f 3 1 2
+        o Unnamed
+        f 1 3 2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        data.objects[2].name = String::new();
+        data.sort_objects_by_name();
+        let names : Vec<&str> = data.objects.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(vec!["","Cube","Test"],names);
+        assert_eq!(vec![3],data.objects[0].primitives);
+        assert_eq!(vec![1,2],data.objects[1].primitives);
+        assert_eq!(vec![0],data.objects[2].primitives);
+
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        let faces = written.find("f 1 3 2").unwrap();
+        let cube = written.find("o Cube").unwrap();
+        let test = written.find("o Test").unwrap();
+        assert!(faces < cube && cube < test);
+    }
+
+    #[test]
+    fn split_by_material() {
+        let obj_str =
+        r#"mtllib materials.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 5 5 5
+        vt 0 0
+        vt 1 1
+        vn 0 0 1
+        o quad
+        g left
+        usemtl red
+        f 1/1/1 2/1/1 3/2/1
+        usemtl blue
+        g right
+        f 1/1 3/2 4/2
+        usemtl red
+        s 1
+        f 3 4 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let parts = data.split_by_material();
+        assert_eq!(2,parts.len());
+
+        let (ref name,ref red) = parts[0];
+        assert_eq!(&Some(String::from("red")),name);
+        assert_eq!(2,red.faces.len());
+        assert_eq!(4,red.vertices.len());
+        assert_eq!(2,red.texcoords.len());
+        assert_eq!(1,red.normals.len());
+        assert_eq!(vec!["red"],red.materials);
+        assert_eq!(vec!["materials.mtl"],red.material_libs);
+        assert_eq!(vec![(0,Some(0),Some(0)), (1,Some(0),Some(0)), (2,Some(1),Some(0))],red.faces[0]);
+        assert_eq!(vec![(2,None,None), (3,None,None), (0,None,None)],red.faces[1]);
+        assert_eq!(Some(0),red.face_material(1));
+        assert_eq!(1,red.face_smoothing_group(1));
+        assert_eq!(vec![0,1],red.objects[0].primitives);
+        assert_eq!(2,red.groups.len());
+
+        let (ref name,ref blue) = parts[1];
+        assert_eq!(&Some(String::from("blue")),name);
+        assert_eq!(1,blue.faces.len());
+        assert_eq!(3,blue.vertices.len());
+        assert!(blue.normals.is_empty());
+        assert_eq!(vec![(0,Some(0),None), (1,Some(1),None), (2,Some(1),None)],blue.faces[0]);
+        assert_eq!(1,blue.groups.len());
+        assert_eq!("right",blue.groups[0].name);
+    }
+
+    #[test]
+    fn lerp() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut scaled = ObjData::load(&mut input).ok().unwrap();
+        scaled.scale_uniform(3.);
+
+        let blend = data.lerp(&scaled,0.5).ok().unwrap();
+        for (b,v) in blend.vertices.iter().zip(&data.vertices) {
+            assert_eq!((v.0*2.,v.1*2.,v.2*2.,v.3),*b);
+        }
+        assert_eq!(data.faces,blend.faces);
+        assert_eq!(data.objects,blend.objects);
+        assert_eq!(data.groups,blend.groups);
+        assert_eq!(data.vertices,data.lerp(&scaled,0.).ok().unwrap().vertices);
+        assert_eq!(scaled.vertices,data.lerp(&scaled,1.).ok().unwrap().vertices);
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vn 1 0 0
+        vn 0 0 1
+        f 1//1 2//1 3//2"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let a = ObjData::load(&mut input).ok().unwrap();
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vn 0 1 0
+        vn 0 0 1
+        f 1//1 2//1 3//2"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let b = ObjData::load(&mut input).ok().unwrap();
+        let blend = a.lerp(&b,0.5).ok().unwrap();
+        let s = 0.5f32.sqrt();
+        assert!((blend.normals[0].0 - s).abs() < 1e-6 && (blend.normals[0].1 - s).abs() < 1e-6);
+        assert_eq!((0.,0.,1.),blend.normals[1]);
+
+        match a.lerp(&ObjData::new(),0.5) {
+            Err(LoadingError::TopologyMismatch(3,0)) => {},
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn to_flat_shaded() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vn 0 0 1
+        o plane
+        f 1/1/1 2/2/1 3/3/1 4/3/1
+        f 1/1 5/2 2/3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let flat = data.to_flat_shaded();
+        let corners : usize = data.faces.iter().map(|f| f.len()).sum();
+        assert_eq!(corners,flat.vertices.len());
+        assert_eq!(vec![(0.,0.,1.), (0.,1.,0.)],flat.normals);
+        assert_eq!(vec![(0,Some(0),Some(0)), (1,Some(1),Some(0)), (2,Some(2),Some(0)), (3,Some(2),Some(0))],flat.faces[0]);
+        assert_eq!(vec![(4,Some(0),Some(1)), (5,Some(1),Some(1)), (6,Some(2),Some(1))],flat.faces[1]);
+        assert_eq!((0.,0.,1.,1.),flat.vertices[5]);
+        assert_eq!(data.texcoords,flat.texcoords);
+        assert_eq!(data.objects,flat.objects);
+    }
+
+    #[test]
+    fn simplify_clustering() {
+        let heights = vec![0.; 17*17];
+        let data = ObjData::from_heightmap(&heights,17,17,(1.,1.,1.));
+        let simplified = data.simplify_clustering(4., false);
+        assert_eq!(512,data.triangles().len());
+        assert!(simplified.triangles().len() < data.triangles().len() / 4);
+        let (min,max) = data.bounds(0..data.vertices.len()).unwrap();
+        let (smin,smax) = simplified.bounds(0..simplified.vertices.len()).unwrap();
+        // Within half a cell on each axis
+        for &d in [sub(smin,min),sub(max,smax)].iter() {
+            assert!(d.0.abs() <= 2. && d.1.abs() <= 2. && d.2.abs() <= 2.);
+        }
+        assert_eq!(1,simplified.objects.len());
+        for f in &simplified.faces {
+            assert!(f.len() >= 3);
+            assert!(f.iter().all(|c| c.0 < simplified.vertices.len() && c.1.is_some()));
+        }
+
+        // A cell containing two vertices of a triangle leaves a degenerate face, which is dropped
+        let obj_str =
+        r#"v 0.1 0.1 0.1
+        v 0.2 0.2 0.2
+        v 1.5 0.5 0.5
+        v 1.5 1.5 0.5
+        f 1 2 3
+        f 1 3 4"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let simplified = data.simplify_clustering(1., false);
+        assert_eq!(vec![vec![(0,None,None), (1,None,None), (2,None,None)]],simplified.faces);
+        assert_eq!(vec![(0.15,0.15,0.15,1.), (1.5,0.5,0.5,1.), (1.5,1.5,0.5,1.)],simplified.vertices);
+        assert_eq!(vec![0],simplified.objects[0].primitives);
+
+        // Two groups with vertices in the same cell, and a vertex shared by both
+        let obj_str =
+        r#"v 0.1 0.1 0.1
+        v 1.5 0.5 0.5
+        v 1.5 1.5 0.5
+        v 0.2 0.2 0.2
+        v -0.5 1.5 0.5
+        g left
+        f 1 2 3
+        g right
+        f 4 3 5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(4,data.simplify_clustering(1., false).vertices.len());
+        let simplified = data.simplify_clustering(1., true);
+        assert_eq!(5,simplified.vertices.len());
+        assert_eq!(simplified.faces[0][2].0,simplified.faces[1][1].0);
+        assert_ne!(simplified.faces[0][0].0,simplified.faces[1][0].0);
+    }
+
+    #[test]
+    fn optimize_face_order() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 2 0 0
+        v 0 1 0
+        v 1 1 0
+        v 2 1 0
+        o first
+        g gr1
+        usemtl red
+        f 1 2 5
+        s 1
+        f 2 3 6
+        l 3 6
+        g gr2
+        usemtl blue
+        f 1 5 4
+        s 2
+        f 2 6 5
+        o second
+        g gr1
+        f 4 5 6"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut optimized = ObjData::load(&mut input).ok().unwrap();
+        optimized.optimize_face_order();
+
+        assert_eq!(data.faces.len(),optimized.faces.len());
+        assert_eq!(vec![(0,None,None), (1,None,None), (4,None,None)],optimized.faces[0]);
+        assert_eq!(vec![(0,None,None), (4,None,None), (3,None,None)],optimized.faces[1]);
+        assert_eq!(vec![0,1,2,3],optimized.objects[0].primitives);
+        assert_eq!(vec![4],optimized.objects[1].primitives);
+        for (i,f) in data.faces.iter().enumerate() {
+            let j = optimized.faces.iter().position(|o| o == f).unwrap();
+            assert_eq!(data.face_material(i),optimized.face_material(j));
+            assert_eq!(data.face_smoothing_group(i),optimized.face_smoothing_group(j));
+            for (g,o) in data.groups.iter().zip(optimized.groups.iter()) {
+                assert_eq!(g.indexes.contains(&i),o.indexes.contains(&j));
+            }
+            for (d,o) in data.objects.iter().zip(optimized.objects.iter()) {
+                assert_eq!(d.primitives.contains(&i),o.primitives.contains(&j));
+            }
+        }
+        assert_eq!(data.faces[1],optimized.faces[optimized.line_contexts[0].faces_before-1]);
+    }
+}
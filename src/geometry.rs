@@ -0,0 +1,1561 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::f32::consts::PI;
+use edit::Axis;
+use obj::{Corner, ObjData};
+use obj::Object;
+
+pub type Vec3 = (f32,f32,f32);
+
+pub fn add(a : Vec3, b : Vec3) -> Vec3 {
+    (a.0+b.0, a.1+b.1, a.2+b.2)
+}
+
+pub fn sub(a : Vec3, b : Vec3) -> Vec3 {
+    (a.0-b.0, a.1-b.1, a.2-b.2)
+}
+
+pub fn scale(a : Vec3, s : f32) -> Vec3 {
+    (a.0*s, a.1*s, a.2*s)
+}
+
+pub fn dot(a : Vec3, b : Vec3) -> f32 {
+    a.0*b.0 + a.1*b.1 + a.2*b.2
+}
+
+pub fn cross(a : Vec3, b : Vec3) -> Vec3 {
+    (a.1*b.2 - a.2*b.1, a.2*b.0 - a.0*b.2, a.0*b.1 - a.1*b.0)
+}
+
+pub fn length(a : Vec3) -> f32 {
+    dot(a,a).sqrt()
+}
+
+/// Angle in radians between `a` and `b`, which must not be null.
+pub fn angle(a : Vec3, b : Vec3) -> f32 {
+    (dot(a,b) / (length(a)*length(b))).clamp(-1.,1.).acos()
+}
+
+/// Length unit a model was likely authored in, as guessed by `suggest_unit`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Unit {
+    Millimeters,
+    Centimeters,
+    Meters,
+}
+
+/// Outcome of the intersection of a ray with a triangle.
+pub enum RayHit {
+    Miss,
+    Hit,
+    /// The origin of the ray lies on the triangle.
+    OnSurface,
+    /// The ray passes too close to an edge or a vertex to be counted reliably.
+    Ambiguous,
+}
+
+/// Intersect the ray `origin + t*dir`, `t >= 0`, with the triangle `tri` (Möller–Trumbore).
+pub fn ray_triangle(origin : Vec3, dir : Vec3, tri : [Vec3;3]) -> RayHit {
+    let eps = 1e-6;
+    let e1 = sub(tri[1],tri[0]);
+    let e2 = sub(tri[2],tri[0]);
+    let scale = length(e1).max(length(e2));
+    let p = cross(dir,e2);
+    let det = dot(e1,p);
+    let s = sub(origin,tri[0]);
+    if det.abs() <= eps * scale * scale {
+        // The ray is parallel to the triangle, check if the origin lies on it.
+        let n = cross(e1,e2);
+        let area = length(n);
+        if area <= eps * scale * scale || dot(s,n).abs() > eps * area {
+            return RayHit::Miss;
+        }
+        let c0 = dot(cross(e1,s),n);
+        let c1 = dot(cross(sub(tri[2],tri[1]),sub(origin,tri[1])),n);
+        let c2 = dot(cross(sub(tri[0],tri[2]),sub(origin,tri[2])),n);
+        if c0 >= 0. && c1 >= 0. && c2 >= 0. {
+            return RayHit::OnSurface;
+        }
+        return RayHit::Miss;
+    }
+    let u = dot(s,p) / det;
+    let q = cross(s,e1);
+    let v = dot(dir,q) / det;
+    let t = dot(e2,q) / det;
+    if u < -eps || v < -eps || u + v > 1. + eps || t < -eps * scale {
+        return RayHit::Miss;
+    }
+    if t <= eps * scale {
+        return RayHit::OnSurface;
+    }
+    if u <= eps || v <= eps || u + v >= 1. - eps {
+        return RayHit::Ambiguous;
+    }
+    RayHit::Hit
+}
+
+/// Distance from `p` to the closest point of the triangle `tri`.
+pub fn point_triangle_distance(p : Vec3, tri : [Vec3;3]) -> f32 {
+    let (a,b,c) = (tri[0],tri[1],tri[2]);
+    let ab = sub(b,a);
+    let ac = sub(c,a);
+    let ap = sub(p,a);
+    let d1 = dot(ab,ap);
+    let d2 = dot(ac,ap);
+    if d1 <= 0. && d2 <= 0. {
+        return length(ap);
+    }
+    let bp = sub(p,b);
+    let d3 = dot(ab,bp);
+    let d4 = dot(ac,bp);
+    if d3 >= 0. && d4 <= d3 {
+        return length(bp);
+    }
+    let cp = sub(p,c);
+    let d5 = dot(ab,cp);
+    let d6 = dot(ac,cp);
+    if d6 >= 0. && d5 <= d6 {
+        return length(cp);
+    }
+    let closest = {
+        let vc = d1*d4 - d3*d2;
+        let vb = d5*d2 - d1*d6;
+        let va = d3*d6 - d5*d4;
+        if vc <= 0. && d1 >= 0. && d3 <= 0. {
+            add(a,scale(ab,d1/(d1-d3)))
+        } else if vb <= 0. && d2 >= 0. && d6 <= 0. {
+            add(a,scale(ac,d2/(d2-d6)))
+        } else if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+            add(b,scale(sub(c,b),(d4-d3)/((d4-d3)+(d5-d6))))
+        } else {
+            let denom = 1. / (va + vb + vc);
+            add(a,add(scale(ab,vb*denom),scale(ac,vc*denom)))
+        }
+    };
+    length(sub(p,closest))
+}
+
+/// Smallest circle `(center,radius)` enclosing the non-empty set `points` (incremental Welzl).
+fn enclosing_circle(points : &[(f32,f32)]) -> ((f32,f32),f32) {
+    let dist = |a : (f32,f32), b : (f32,f32)| ((a.0-b.0)*(a.0-b.0) + (a.1-b.1)*(a.1-b.1)).sqrt();
+    let diameter = |a : (f32,f32), b : (f32,f32)| (((a.0+b.0)/2.,(a.1+b.1)/2.), dist(a,b)/2.);
+    let circumcircle = |a : (f32,f32), b : (f32,f32), c : (f32,f32)| {
+        let (bx,by) = (b.0-a.0,b.1-a.1);
+        let (cx,cy) = (c.0-a.0,c.1-a.1);
+        let d = 2.*(bx*cy - by*cx);
+        if d.abs() < 1e-12 {
+            // Collinear points, the circle on the two farthest ones encloses the third
+            let circles = [diameter(a,b),diameter(b,c),diameter(a,c)];
+            return circles.iter().cloned().fold(circles[0], |m,c| if c.1 > m.1 {c} else {m});
+        }
+        let ux = (cy*(bx*bx + by*by) - by*(cx*cx + cy*cy)) / d;
+        let uy = (bx*(cx*cx + cy*cy) - cx*(bx*bx + by*by)) / d;
+        ((a.0+ux,a.1+uy), (ux*ux + uy*uy).sqrt())
+    };
+    let outside = |circle : ((f32,f32),f32), p : (f32,f32)| dist(circle.0,p) > circle.1 * (1. + 1e-6) + 1e-6;
+    let mut circle = (points[0],0.);
+    for i in 1..points.len() {
+        if !outside(circle,points[i]) {
+            continue;
+        }
+        circle = (points[i],0.);
+        for j in 0..i {
+            if !outside(circle,points[j]) {
+                continue;
+            }
+            circle = diameter(points[i],points[j]);
+            for k in 0..j {
+                if outside(circle,points[k]) {
+                    circle = circumcircle(points[i],points[j],points[k]);
+                }
+            }
+        }
+    }
+    circle
+}
+
+/// Triangles of the convex hull of `points`, oriented outward.
+///
+/// Returns `None` if the points do not span a volume.
+fn convex_hull_triangles(points : &[Vec3]) -> Option<Vec<[usize;3]>> {
+    if points.len() < 4 {
+        return None;
+    }
+    let farthest = |dist : &dyn Fn(Vec3) -> f32| {
+        let mut best = 0;
+        for i in 1..points.len() {
+            if dist(points[i]) > dist(points[best]) {
+                best = i;
+            }
+        }
+        best
+    };
+    // Initial tetrahedron
+    let p0 = farthest(&|p : Vec3| -p.0);
+    let p1 = farthest(&|p : Vec3| length(sub(p,points[p0])));
+    let d01 = sub(points[p1],points[p0]);
+    let scale = length(d01);
+    let eps = 1e-5 * scale;
+    if scale <= 0. {
+        return None;
+    }
+    let p2 = farthest(&|p : Vec3| length(cross(d01,sub(p,points[p0]))));
+    let normal = cross(d01,sub(points[p2],points[p0]));
+    if length(normal) <= eps * scale {
+        return None;
+    }
+    let p3 = farthest(&|p : Vec3| dot(normal,sub(p,points[p0])).abs());
+    let side = dot(normal,sub(points[p3],points[p0]));
+    if side.abs() <= eps * length(normal) {
+        return None;
+    }
+    let mut faces = if side < 0. {
+        vec![[p0,p1,p2], [p0,p3,p1], [p1,p3,p2], [p2,p3,p0]]
+    } else {
+        vec![[p0,p2,p1], [p0,p1,p3], [p1,p2,p3], [p2,p0,p3]]
+    };
+    let above = |f : &[usize;3], p : Vec3| {
+        let n = cross(sub(points[f[1]],points[f[0]]),sub(points[f[2]],points[f[0]]));
+        dot(n,sub(p,points[f[0]])) > eps * length(n)
+    };
+    for (i,&p) in points.iter().enumerate() {
+        if i == p0 || i == p1 || i == p2 || i == p3 {
+            continue;
+        }
+        let (visible,kept) : (Vec<[usize;3]>,Vec<[usize;3]>) = faces.into_iter().partition(|f| above(f,p));
+        faces = kept;
+        if visible.is_empty() {
+            continue;
+        }
+        let mut edges = Vec::new();
+        for f in &visible {
+            for k in 0..3 {
+                edges.push((f[k],f[(k+1)%3]));
+            }
+        }
+        for &(a,b) in &edges {
+            if !edges.contains(&(b,a)) {
+                faces.push([a,b,i]);
+            }
+        }
+    }
+    Some(faces)
+}
+
+impl ObjData {
+    /// Position `(x,y,z)` of the vertex at index `v`, ignoring its weight.
+    pub(crate) fn position(&self, v : usize) -> Vec3 {
+        let (x,y,z,_) = self.vertices[v];
+        (x,y,z)
+    }
+
+    /// Corners of the triangles of the face `f`, triangulated as a fan, leaving out the
+    /// triangles with a vertex index past the end of `vertices`.
+    pub(crate) fn fan<'a>(&'a self, f : &'a [Corner]) -> impl Iterator<Item=[Corner;3]> + 'a {
+        (1..f.len().saturating_sub(1)).map(move |k| [f[0],f[k],f[k+1]])
+            .filter(move |t| t.iter().all(|c| c.0 < self.vertices.len()))
+    }
+
+    /// Vertex indices of the triangles of the faces, triangulated as fans.
+    pub(crate) fn triangles(&self) -> Vec<[usize;3]> {
+        let mut triangles = Vec::new();
+        for f in &self.faces {
+            for k in 1..f.len().saturating_sub(1) {
+                triangles.push([f[0].0,f[k].0,f[k+1].0]);
+            }
+        }
+        triangles
+    }
+
+    /// Normal of the face `i` computed with Newell's method, of length twice its projected area.
+    fn newell_normal(&self, i : usize) -> Vec3 {
+        let f = &self.faces[i];
+        let mut n = (0.,0.,0.);
+        for k in 0..f.len() {
+            let a = self.position(f[k].0);
+            let b = self.position(f[(k+1)%f.len()].0);
+            n = add(n,((a.1-b.1)*(a.2+b.2), (a.2-b.2)*(a.0+b.0), (a.0-b.0)*(a.1+b.1)));
+        }
+        n
+    }
+
+    /// Unit normal of the face `i` computed with Newell's method, null if the face is degenerate.
+    pub(crate) fn face_normal(&self, i : usize) -> Vec3 {
+        let n = self.newell_normal(i);
+        let l = length(n);
+        if l > 0. {scale(n,1./l)} else {(0.,0.,0.)}
+    }
+
+    /// Area of the face `i`, sum of the areas of its triangles.
+    pub(crate) fn face_area(&self, i : usize) -> f32 {
+        let f = &self.faces[i];
+        let mut area = 0.;
+        for k in 1..f.len().saturating_sub(1) {
+            let a = self.position(f[0].0);
+            let ab = sub(self.position(f[k].0),a);
+            let ac = sub(self.position(f[k+1].0),a);
+            area += length(cross(ab,ac)) / 2.;
+        }
+        area
+    }
+
+    /// Bounding box `(min,max)` of the given vertices, `None` if there is none.
+    pub(crate) fn bounds<I : Iterator<Item=usize>>(&self, vertices : I) -> Option<(Vec3,Vec3)> {
+        let mut bounds : Option<(Vec3,Vec3)> = None;
+        for v in vertices {
+            let p = self.position(v);
+            bounds = Some(match bounds {
+                Some((min,max)) => ((min.0.min(p.0), min.1.min(p.1), min.2.min(p.2)),
+                                    (max.0.max(p.0), max.1.max(p.1), max.2.max(p.2))),
+                None => (p,p),
+            });
+        }
+        bounds
+    }
+
+    /// Axis-aligned bounding box `(min,max)` of the face `face_index`.
+    ///
+    /// Returns `None` if the face does not exist or has no vertex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 1\nv 0 -1 0\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.face_bounding_box(0), Some(((0.,-1.,0.),(2.,0.,1.))));
+    /// assert_eq!(data.face_bounding_box(1), None);
+    /// ```
+    pub fn face_bounding_box(&self, face_index : usize) -> Option<((f32,f32,f32),(f32,f32,f32))> {
+        self.faces.get(face_index).and_then(|f| self.bounds(f.iter().map(|c| c.0)))
+    }
+
+    /// Largest dimension of the bounding box of the vertices, `0` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 1\nv 0 -5 0".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.estimated_scale(), 5.);
+    /// ```
+    pub fn estimated_scale(&self) -> f32 {
+        match self.bounds(0..self.vertices.len()) {
+            Some((min,max)) => (max.0-min.0).max(max.1-min.1).max(max.2-min.2),
+            None => 0.,
+        }
+    }
+
+    /// Guess the unit of the model from `estimated_scale`, assuming it is an object of human
+    /// size: from `1000` on it is read as millimeters, from `100` on as centimeters, and
+    /// as meters below.
+    ///
+    /// This is only a heuristic for importers reconciling unit systems, a building modeled in
+    /// meters is reported in centimeters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{ObjData, Unit};
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 0 180 0".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.suggest_unit(), Unit::Centimeters);
+    /// ```
+    pub fn suggest_unit(&self) -> Unit {
+        let scale = self.estimated_scale();
+        if scale >= 1000. {
+            Unit::Millimeters
+        } else if scale >= 100. {
+            Unit::Centimeters
+        } else {
+            Unit::Meters
+        }
+    }
+
+    /// Area of the face `face_index` in texture space, computed from the `(u,v)` of its corners.
+    ///
+    /// Returns `None` if the face does not exist or if one of its corners has no texture coordinates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 0.5 0\nvt 0 0.5\nf 1/1 2/2 3/3\nf 1 2 3";
+    /// let mut input = BufReader::new(obj.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.face_uv_area(0), Some(0.125));
+    /// assert_eq!(data.face_uv_area(1), None);
+    /// ```
+    pub fn face_uv_area(&self, face_index : usize) -> Option<f32> {
+        let f = self.faces.get(face_index)?;
+        let mut uvs = Vec::with_capacity(f.len());
+        for &(_,vt,_) in f {
+            let (u,v,_) = self.texcoords[vt?];
+            uvs.push((u,v));
+        }
+        let mut area = 0.;
+        for (k,a) in uvs.iter().enumerate() {
+            let b = uvs[(k+1)%uvs.len()];
+            area += a.0*b.1 - b.0*a.1;
+        }
+        Some(area.abs() / 2.)
+    }
+
+    /// Sum of the oriented areas `cross(e1,e2)/2` of the triangles of the faces, triangulated as fans.
+    ///
+    /// It is null for a closed mesh, whatever its orientation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nf 1 2 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.area_vector(), (0.,0.,4.));
+    /// ```
+    pub fn area_vector(&self) -> (f32,f32,f32) {
+        self.triangles().iter().fold((0.,0.,0.), |sum,t| {
+            let a = self.position(t[0]);
+            let n = cross(sub(self.position(t[1]),a),sub(self.position(t[2]),a));
+            add(sum,scale(n,0.5))
+        })
+    }
+
+    /// Area and unit normal of each face, computed together with Newell's method, for
+    /// area-weighted vertex normals.
+    ///
+    /// The area is the one of the face projected on its plane, which is its area when it
+    /// is planar. Degenerate faces have a null area and normal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nf 1 2 3 4\nf 1 2 1".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.face_area_normals(), vec![(4.,(0.,0.,1.)), (0.,(0.,0.,0.))]);
+    /// ```
+    pub fn face_area_normals(&self) -> Vec<(f32,(f32,f32,f32))> {
+        (0..self.faces.len()).map(|i| {
+            let n = self.newell_normal(i);
+            let l = length(n);
+            if l > 0. {(l/2.,scale(n,1./l))} else {(0.,(0.,0.,0.))}
+        }).collect()
+    }
+
+    /// Signed volume enclosed by each connected component of the faces, ordered by their first face.
+    ///
+    /// The volume is positive when the faces of the component are wound counter-clockwise seen
+    /// from outside, negative for an inner void wound the other way. It is only meaningful for
+    /// closed components.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let volumes = data.shell_volumes();
+    /// assert_eq!(volumes.len(), 1);
+    /// assert!((volumes[0] - 8.).abs() < 1e-5);
+    /// ```
+    pub fn shell_volumes(&self) -> Vec<f32> {
+        self.component_face_sets().iter().map(|faces| {
+            let mut volume = 0.;
+            for &i in faces {
+                let f = &self.faces[i];
+                for k in 1..f.len().saturating_sub(1) {
+                    let a = self.position(f[0].0);
+                    let b = self.position(f[k].0);
+                    let c = self.position(f[k+1].0);
+                    volume += dot(a,cross(b,c)) / 6.;
+                }
+            }
+            volume
+        }).collect()
+    }
+
+    /// Index of the face with the greatest area, the first one in case of a tie.
+    ///
+    /// Returns `None` if there is no face.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 2 0\nf 1 2 3\nf 1 2 4";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.largest_face(), Some(1));
+    /// ```
+    pub fn largest_face(&self) -> Option<usize> {
+        let mut largest : Option<(usize,f32)> = None;
+        for i in 0..self.faces.len() {
+            let area = self.face_area(i);
+            if largest.map_or(true, |(_,a)| area > a) {
+                largest = Some((i,area));
+            }
+        }
+        largest.map(|(i,_)| i)
+    }
+
+    /// Least-squares plane `(n,d)`, with `dot(n,p) = d` and `n` unit, through the vertices of the given faces.
+    ///
+    /// The normal is oriented along the summed normals of the faces when they do not cancel out.
+    /// Returns `None` if the vertices are less than three or collinear. Invalid face indices are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 2\nv 1 0 2\nv 0 1 2\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.best_fit_plane(&[0]), Some(((0.,0.,1.),2.)));
+    /// ```
+    pub fn best_fit_plane(&self, face_indices : &[usize]) -> Option<((f32,f32,f32),f32)> {
+        let faces : Vec<usize> = face_indices.iter().cloned().filter(|&i| i < self.faces.len()).collect();
+        let mut vertices : Vec<usize> = faces.iter().flat_map(|&i| self.faces[i].iter().map(|c| c.0)).collect();
+        vertices.sort();
+        vertices.dedup();
+        if vertices.len() < 3 {
+            return None;
+        }
+        let centroid = scale(vertices.iter().fold((0.,0.,0.), |c,&v| add(c,self.position(v))), 1. / vertices.len() as f32);
+        let (mut xx, mut xy, mut xz, mut yy, mut yz, mut zz) = (0.,0.,0.,0.,0.,0.);
+        for &v in &vertices {
+            let r = sub(self.position(v),centroid);
+            xx += r.0*r.0;
+            xy += r.0*r.1;
+            xz += r.0*r.2;
+            yy += r.1*r.1;
+            yz += r.1*r.2;
+            zz += r.2*r.2;
+        }
+        // Solve the normal equations with the component of the normal having the largest determinant set to one
+        let det_x = yy*zz - yz*yz;
+        let det_y = xx*zz - xz*xz;
+        let det_z = xx*yy - xy*xy;
+        let det_max = det_x.max(det_y).max(det_z);
+        let spread = xx + yy + zz;
+        if det_max <= 1e-12 * spread * spread {
+            return None;
+        }
+        let mut n = if det_max == det_x {
+            (det_x, xz*yz - xy*zz, xy*yz - xz*yy)
+        } else if det_max == det_y {
+            (xz*yz - xy*zz, det_y, xy*xz - yz*xx)
+        } else {
+            (xy*yz - xz*yy, xy*xz - yz*xx, det_z)
+        };
+        n = scale(n, 1. / length(n));
+        let orientation = faces.iter().fold((0.,0.,0.), |s,&i| add(s,self.face_normal(i)));
+        if dot(n,orientation) < 0. {
+            n = scale(n, -1.);
+        }
+        Some((n, dot(n,centroid)))
+    }
+
+    /// Minimum, maximum and mean length of the unique edges of the faces, `None` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 3 0 0\nv 0 4 0\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.edge_length_stats(), Some((3.,5.,4.)));
+    /// ```
+    pub fn edge_length_stats(&self) -> Option<(f32,f32,f32)> {
+        let edges = self.unique_edges();
+        if edges.is_empty() {
+            return None;
+        }
+        let mut stats = (f32::INFINITY,0f32,0f32);
+        for &(a,b) in &edges {
+            let l = length(sub(self.position(b),self.position(a)));
+            stats = (stats.0.min(l),stats.1.max(l),stats.2 + l);
+        }
+        Some((stats.0,stats.1,stats.2 / edges.len() as f32))
+    }
+
+    /// Sum of the lengths of the unique edges of the faces, i.e. the length of the wireframe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 3 0 0\nv 0 4 0\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.total_edge_length(), 12.);
+    /// ```
+    pub fn total_edge_length(&self) -> f32 {
+        self.build_edge_map().keys().map(|&(a,b)| length(sub(self.position(b),self.position(a)))).sum()
+    }
+
+    /// Smallest cylinder along the up axis `axis` enclosing the vertices, `None` if there is none.
+    ///
+    /// Returns its height, its radius and the center of its base in the perpendicular plane,
+    /// `(x,z)` for `Yup` and `(x,y)` for `Zup`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::{Axis, ObjData};
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let (height,radius,center) = data.bounding_cylinder(Axis::Yup).unwrap();
+    /// assert_eq!((height,center), (2.,(0.,0.)));
+    /// assert!((radius - 2f32.sqrt()).abs() < 1e-6);
+    /// ```
+    pub fn bounding_cylinder(&self, axis : Axis) -> Option<(f32,f32,(f32,f32))> {
+        let (min,max) = self.bounds(0..self.vertices.len())?;
+        let (height,points) : (f32,Vec<(f32,f32)>) = match axis {
+            Axis::Yup => (max.1 - min.1, self.vertices.iter().map(|v| (v.0,v.2)).collect()),
+            Axis::Zup => (max.2 - min.2, self.vertices.iter().map(|v| (v.0,v.1)).collect()),
+        };
+        let (center,radius) = enclosing_circle(&points);
+        Some((height,radius,center))
+    }
+
+    /// Find the faces having two consecutive corners closer than `epsilon`.
+    ///
+    /// The edge closing the face, from its last corner to its first one, is also checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\nf 1 2 3 4";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.faces_with_zero_edges(1e-6), vec![1]);
+    /// ```
+    pub fn faces_with_zero_edges(&self, epsilon : f32) -> Vec<usize> {
+        self.faces.iter().enumerate().filter(|&(_,f)| {
+            f.iter().enumerate().any(|(k,c)| {
+                let next = f[(k+1)%f.len()].0;
+                length(sub(self.position(next),self.position(c.0))) <= epsilon
+            })
+        }).map(|(i,_)| i).collect()
+    }
+
+    /// Find the faces having a triangle of area at most `epsilon` made of collinear vertices.
+    ///
+    /// Faces are triangulated as fans. Triangles with an edge shorter than `epsilon`, caught
+    /// by `faces_with_zero_edges`, are not reported here.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 2 0 0\nv 0 1 0\nf 1 2 4\nf 1 2 3";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.find_collinear_faces(1e-6), vec![1]);
+    /// ```
+    pub fn find_collinear_faces(&self, epsilon : f32) -> Vec<usize> {
+        self.faces.iter().enumerate().filter(|&(_,f)| {
+            (1..f.len().saturating_sub(1)).any(|k| {
+                let (a,b,c) = (self.position(f[0].0),self.position(f[k].0),self.position(f[k+1].0));
+                let edges = [sub(b,a),sub(c,b),sub(a,c)];
+                edges.iter().all(|&e| length(e) > epsilon) && length(cross(edges[0],edges[2])) / 2. <= epsilon
+            })
+        }).map(|(i,_)| i).collect()
+    }
+
+    /// Compute the centroid of the vertices referenced by the faces of each group.
+    ///
+    /// Each vertex is counted once per group, even if several faces share it.
+    /// Groups without any face are not part of the result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 2 0 0\nv 0 2 0\ng tri\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let centroids = data.group_centroids();
+    /// assert_eq!(centroids["tri"], (2./3.,2./3.,0.));
+    /// ```
+    pub fn group_centroids(&self) -> HashMap<String,(f32,f32,f32)> {
+        let mut centroids = HashMap::new();
+        for g in &self.groups {
+            let mut indexes : HashSet<usize> = HashSet::new();
+            for f in &g.indexes {
+                for &(v,_,_) in &self.faces[*f] {
+                    indexes.insert(v);
+                }
+            }
+            if indexes.is_empty() {
+                continue;
+            }
+            let mut sum = (0.,0.,0.);
+            for v in &indexes {
+                let (x,y,z,_) = self.vertices[*v];
+                sum = (sum.0+x, sum.1+y, sum.2+z);
+            }
+            let n = indexes.len() as f32;
+            centroids.insert(g.name.clone(), (sum.0/n, sum.1/n, sum.2/n));
+        }
+        centroids
+    }
+
+    /// Find T-junctions, i.e. vertices lying on the edge of a face without being one of its ends.
+    ///
+    /// Returns each offending vertex with the edge `(a,b)` it lies on, `a < b`.
+    /// A vertex is considered on an edge if its distance to the segment is at most `epsilon`
+    /// and it is not within `epsilon` of one of its ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 2 0 0\nv 1 1 0\nv 1 0 0\nv 1 -1 0\nf 1 2 3\nf 1 4 5\nf 4 2 5";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.find_t_junctions(1e-5), vec![(3,(0,1))]);
+    /// ```
+    pub fn find_t_junctions(&self, epsilon : f32) -> Vec<(usize,(usize,usize))> {
+        let edges = self.unique_edges();
+        let mut used : Vec<usize> = self.faces.iter().flat_map(|f| f.iter().map(|&(v,_,_)| v)).collect();
+        used.sort();
+        used.dedup();
+        let mut junctions = Vec::new();
+        for v in used {
+            let p = self.position(v);
+            for &(a,b) in &edges {
+                if v == a || v == b {
+                    continue;
+                }
+                let pa = self.position(a);
+                let ab = sub(self.position(b), pa);
+                let len = length(ab);
+                if len <= 2.*epsilon {
+                    continue;
+                }
+                let t = dot(sub(p,pa),ab) / len;
+                if t <= epsilon || t >= len - epsilon {
+                    continue;
+                }
+                let proj = add(pa, scale(ab, t/len));
+                if length(sub(p,proj)) <= epsilon {
+                    junctions.push((v,(a,b)));
+                }
+            }
+        }
+        junctions
+    }
+
+    /// Silhouette edges `(a,b)`, `a < b`, seen along `view_dir`, sorted.
+    ///
+    /// An edge is on the silhouette when it is shared by two faces, one facing the viewer and
+    /// the other facing away, i.e. their normals have dot products of opposite signs with `view_dir`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.silhouette_edges((1.,1.,1.)).len(), 6);
+    /// ```
+    pub fn silhouette_edges(&self, view_dir : (f32,f32,f32)) -> Vec<(usize,usize)> {
+        let mut edges : Vec<(usize,usize)> = self.build_edge_map().into_iter()
+            .filter(|(_,faces)| faces.len() == 2 && {
+                let a = dot(self.face_normal(faces[0]),view_dir);
+                let b = dot(self.face_normal(faces[1]),view_dir);
+                (a < 0. && b > 0.) || (a > 0. && b < 0.)
+            })
+            .map(|(e,_)| e)
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    /// Generalized winding number of the faces around `point`, their solid angle seen from
+    /// `point` divided by `4π`.
+    ///
+    /// It is `1` inside a closed mesh with outward faces, `0` outside, and varies smoothly
+    /// when the mesh has holes, so that `> 0.5` remains a robust inside test. Points on the
+    /// surface get an arbitrary value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert!((data.winding_number((0.,0.,0.)) - 1.).abs() < 1e-5);
+    /// assert!(data.winding_number((3.,0.,0.)).abs() < 1e-5);
+    /// ```
+    pub fn winding_number(&self, point : Vec3) -> f32 {
+        let mut solid_angle = 0.;
+        for t in self.triangles() {
+            let a = sub(self.position(t[0]),point);
+            let b = sub(self.position(t[1]),point);
+            let c = sub(self.position(t[2]),point);
+            let (la,lb,lc) = (length(a),length(b),length(c));
+            let det = dot(a,cross(b,c));
+            let div = la*lb*lc + dot(a,b)*lc + dot(a,c)*lb + dot(b,c)*la;
+            solid_angle += 2. * det.atan2(div);
+        }
+        solid_angle / (4. * PI)
+    }
+
+    /// Test if `point` is inside the volume enclosed by the faces, assuming the mesh is closed.
+    ///
+    /// A ray is cast from `point` along `+x` and the intersections with the triangulated
+    /// faces are counted, an odd count meaning inside.
+    /// Points lying on the surface are considered inside.
+    /// When the ray grazes an edge or a vertex, it is cast again in a slightly tilted
+    /// direction so the result does not depend on the triangulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert!(data.contains_point((0.,0.,0.)));
+    /// assert!(!data.contains_point((3.,0.,0.)));
+    /// ```
+    pub fn contains_point(&self, point : Vec3) -> bool {
+        let directions = [(1.,0.,0.), (1.,0.0137,0.0071), (1.,-0.0093,0.0213), (1.,0.0311,-0.0187)];
+        let triangles = self.triangles();
+        let mut inside = false;
+        for dir in directions.iter() {
+            let mut count = 0;
+            let mut ambiguous = false;
+            for t in &triangles {
+                let tri = [self.position(t[0]),self.position(t[1]),self.position(t[2])];
+                match ray_triangle(point, *dir, tri) {
+                    RayHit::Miss => {},
+                    RayHit::Hit => count += 1,
+                    RayHit::OnSurface => return true,
+                    RayHit::Ambiguous => {
+                        ambiguous = true;
+                        break;
+                    },
+                }
+            }
+            inside = count % 2 == 1;
+            if !ambiguous {
+                break;
+            }
+        }
+        inside
+    }
+
+    /// Distance from `point` to the surface, negative inside the volume enclosed by the faces.
+    ///
+    /// The distance is the one to the nearest triangle and the sign comes from `contains_point`,
+    /// so the mesh is assumed closed. Points on the surface give `0`, and `+inf` is returned
+    /// when there is no face.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.signed_distance((0.,0.,0.)), -1.);
+    /// assert_eq!(data.signed_distance((3.,0.,0.)), 2.);
+    /// ```
+    pub fn signed_distance(&self, point : (f32,f32,f32)) -> f32 {
+        let distance = self.triangles().iter()
+            .map(|t| point_triangle_distance(point, [self.position(t[0]),self.position(t[1]),self.position(t[2])]))
+            .fold(f32::INFINITY, f32::min);
+        if distance > 0. && self.contains_point(point) {-distance} else {distance}
+    }
+
+    /// Estimate the Gaussian curvature at each vertex with the angle deficit.
+    ///
+    /// The curvature of a vertex is `(2π - Σθ) / A` where `Σθ` is the sum of the angles of
+    /// the triangles at the vertex and `A` a third of their area. For vertices on a boundary
+    /// `π` is used instead of `2π`, so flat regions have a curvature near zero, except at the
+    /// corners of their boundary. Vertices not used by any face have a null curvature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv -1 0 0\nv 0 -1 0\nf 1 2 3\nf 1 3 4\nf 1 4 5\nf 1 5 2";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert!(data.vertex_curvature()[0].abs() < 1e-5);
+    /// ```
+    pub fn vertex_curvature(&self) -> Vec<f32> {
+        let triangles = self.triangles();
+        let mut angles = vec![0f32; self.vertices.len()];
+        let mut areas = vec![0f32; self.vertices.len()];
+        let mut edges : HashMap<(usize,usize),usize> = HashMap::new();
+        for t in &triangles {
+            let p = [self.position(t[0]),self.position(t[1]),self.position(t[2])];
+            let area = length(cross(sub(p[1],p[0]),sub(p[2],p[0]))) / 2.;
+            for k in 0..3 {
+                let e1 = sub(p[(k+1)%3],p[k]);
+                let e2 = sub(p[(k+2)%3],p[k]);
+                if length(e1) > 0. && length(e2) > 0. {
+                    angles[t[k]] += angle(e1,e2);
+                }
+                areas[t[k]] += area / 3.;
+                let (a,b) = (t[k],t[(k+1)%3]);
+                *edges.entry((a.min(b),a.max(b))).or_insert(0) += 1;
+            }
+        }
+        let mut boundary = vec![false; self.vertices.len()];
+        for (&(a,b),&n) in &edges {
+            if n == 1 {
+                boundary[a] = true;
+                boundary[b] = true;
+            }
+        }
+        (0..self.vertices.len()).map(|v| {
+            if areas[v] <= 0. {
+                return 0.;
+            }
+            let full = if boundary[v] {PI} else {2.*PI};
+            (full - angles[v]) / areas[v]
+        }).collect()
+    }
+
+    /// Compute the convex hull of the vertices as a new `ObjData`.
+    ///
+    /// The hull is built incrementally and made of triangles oriented outward, stored in a
+    /// single unnamed object. Only the vertices of the hull are kept, in their original order.
+    /// If the vertices are all coplanar the hull has no volume and the result is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let hull = data.convex_hull();
+    /// assert_eq!(hull.vertices.len(), 8);
+    /// assert_eq!(hull.faces.len(), 12);
+    /// ```
+    pub fn convex_hull(&self) -> ObjData {
+        let mut hull = ObjData::new();
+        let points : Vec<Vec3> = (0..self.vertices.len()).map(|v| self.position(v)).collect();
+        let faces = match convex_hull_triangles(&points) {
+            Some(faces) => faces,
+            None => return hull,
+        };
+        let mut remap : Vec<Option<usize>> = vec![None; points.len()];
+        for f in &faces {
+            for &v in f {
+                remap[v] = Some(0);
+            }
+        }
+        for (v,r) in remap.iter_mut().enumerate() {
+            if r.is_some() {
+                *r = Some(hull.vertices.len());
+                hull.vertices.push(self.vertices[v]);
+            }
+        }
+        for f in &faces {
+            hull.faces.push(f.iter().map(|&v| (remap[v].unwrap(),None,None)).collect());
+        }
+        let mut obj = Object::new(String::new());
+        obj.primitives = (0..hull.faces.len()).collect();
+        hull.objects.push(obj);
+        hull
+    }
+
+    /// Build a grid mesh from the `width` by `height` samples of `heights`, stored row by row.
+    ///
+    /// The sample at column `x` and row `y` becomes the vertex `(x, h, y)` scaled by `scale`,
+    /// with the texture coordinates `(x/(width-1), y/(height-1))`. Each cell of the grid gives
+    /// a quad facing up, and all of them are put in an unnamed object.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `heights` has less than `width*height` samples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_heightmap(&[0., 1., 2., 3.], 2, 2, (1.,0.5,1.));
+    /// assert_eq!(data.vertices[3], (1.,1.5,1.,1.));
+    /// assert_eq!(data.faces[0], vec![(0,Some(0),None), (2,Some(2),None), (3,Some(3),None), (1,Some(1),None)]);
+    /// ```
+    pub fn from_heightmap(heights : &[f32], width : usize, height : usize, scale : (f32,f32,f32)) -> ObjData {
+        assert!(heights.len() >= width*height, "not enough samples in the heightmap");
+        let mut data = ObjData::new();
+        let step = |n : usize, i : usize| if n > 1 {i as f32 / (n-1) as f32} else {0.};
+        for y in 0..height {
+            for x in 0..width {
+                let h = heights[y*width+x];
+                data.vertices.push((x as f32 * scale.0, h * scale.1, y as f32 * scale.2, 1.));
+                data.texcoords.push((step(width,x), step(height,y), 0.));
+            }
+        }
+        for y in 1..height {
+            for x in 1..width {
+                let cell = [(y-1)*width+x-1, y*width+x-1, y*width+x, (y-1)*width+x];
+                data.faces.push(cell.iter().map(|&v| (v,Some(v),None)).collect());
+            }
+        }
+        let mut obj = Object::new(String::new());
+        obj.primitives = (0..data.faces.len()).collect();
+        data.objects.push(obj);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edit::Axis;
+    use std::fs::File;
+    use std::io::BufReader;
+    use obj::*;
+    use super::*;
+
+    #[test]
+    fn group_centroids() {
+        let obj_str =
+        r#"v 0 0 0
+        v 2 0 0
+        v 2 2 0
+        v 0 2 0
+        v 0 0 4
+        g gr1 gr2
+        f 1 2 3
+        g gr1
+        f 1 3 4
+        g gr3
+        f 1 2 5
+        g gr4"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let centroids = data.group_centroids();
+        assert_eq!(3, centroids.len());
+        assert_eq!((1.,1.,0.), centroids["gr1"]);
+        assert_eq!((4./3.,2./3.,0.), centroids["gr2"]);
+        assert_eq!((2./3.,0.,4./3.), centroids["gr3"]);
+        assert!(!centroids.contains_key("gr4"));
+    }
+
+    #[test]
+    fn find_t_junctions() {
+        // The top triangle spans the whole bottom edge which is split by vertex 4 below.
+        let obj_str =
+        r#"v 0 0 0
+        v 2 0 0
+        v 1 1 0
+        v 1 0 0
+        v 1 -1 0
+        f 1 2 3
+        f 1 4 5
+        f 4 2 5"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![(3,(0,1))],data.find_t_junctions(1e-5));
+
+        let obj_str =
+        r#"v 0 0 0
+        v 2 0 0
+        v 1 1 0
+        v 1 -1 0
+        f 1 2 3
+        f 1 4 2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.find_t_junctions(1e-5).is_empty());
+    }
+
+    #[test]
+    fn convex_hull() {
+        let obj_str =
+        r#"v 1 -1 -1
+        v 1 -1 1
+        v -1 -1 1
+        v 0.2 0.1 -0.3
+        v -1 -1 -1
+        v 1 1 -1
+        v 1 1 1
+        v -1 1 1
+        v -1 1 -1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let hull = data.convex_hull();
+        let mut expected = data.vertices.clone();
+        expected.remove(3);
+        assert_eq!(expected,hull.vertices);
+        assert_eq!(12,hull.faces.len());
+        assert_eq!(vec![(0..12).collect::<Vec<_>>()],hull.objects.iter().map(|o| o.primitives.clone()).collect::<Vec<_>>());
+        for f in &hull.faces {
+            assert_eq!(3,f.len());
+            // Every face lies on a side of the cube and points outward.
+            let a = hull.position(f[0].0);
+            let n = cross(sub(hull.position(f[1].0),a),sub(hull.position(f[2].0),a));
+            assert!((length(n) - 4.).abs() < 1e-5);
+            assert!((dot(n,a) - 4.).abs() < 1e-5);
+        }
+        assert!(hull.contains_point((0.2,0.1,-0.3)));
+
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.convex_hull().faces.is_empty());
+    }
+
+    #[test]
+    fn from_heightmap() {
+        let heights = [0., 1., 0.,
+                       1., 2., 1.,
+                       0., 1., 0.];
+        let data = ObjData::from_heightmap(&heights, 3, 3, (2.,1.,2.));
+        assert_eq!(9,data.vertices.len());
+        assert_eq!(9,data.texcoords.len());
+        assert_eq!(4,data.faces.len());
+        assert_eq!(vec![0,1,2,3],data.objects[0].primitives);
+        assert_eq!((2.,2.,2.,1.),data.vertices[4]);
+        assert_eq!((0.5,0.5,0.),data.texcoords[4]);
+        assert_eq!((1.,1.,0.),data.texcoords[8]);
+        for i in 0..data.faces.len() {
+            assert!(data.face_normal(i).1 > 0.);
+        }
+        assert_eq!(0,ObjData::from_heightmap(&heights, 3, 1, (1.,1.,1.)).faces.len());
+    }
+
+    #[test]
+    fn signed_distance() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(-1.,data.signed_distance((0.,0.,0.)));
+        assert!((data.signed_distance((0.5,0.2,-0.3)) + 0.5).abs() < 1e-6);
+        assert!((data.signed_distance((0.,3.,0.)) - 2.).abs() < 1e-6);
+        assert!((data.signed_distance((2.,2.,2.)) - 3f32.sqrt()).abs() < 1e-6);
+        assert!((data.signed_distance((2.,2.,0.)) - 2f32.sqrt()).abs() < 1e-6);
+        assert_eq!(0.,data.signed_distance((1.,0.,0.)));
+        assert_eq!(f32::INFINITY,ObjData::new().signed_distance((0.,0.,0.)));
+    }
+
+    #[test]
+    fn vertex_curvature() {
+        // A flat 3x3 grid of vertices with quads.
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 2 0 0
+        v 0 1 0
+        v 1 1 0
+        v 2 1 0
+        v 0 2 0
+        v 1 2 0
+        v 2 2 0
+        v 5 5 5
+        f 1 2 5 4
+        f 2 3 6 5
+        f 4 5 8 7
+        f 5 6 9 8"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let curvature = data.vertex_curvature();
+        assert_eq!(10,curvature.len());
+        for &v in [1,3,4,5,7,9].iter() {
+            assert!(curvature[v].abs() < 1e-5);
+        }
+        for &v in [0,2,6,8].iter() {
+            assert!(curvature[v] > 0.);
+        }
+
+        // The corners of a cube all have the same positive curvature.
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let curvature = data.vertex_curvature();
+        for k in &curvature {
+            assert!(*k > 0.);
+        }
+    }
+
+    #[test]
+    fn face_bounding_box() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(((-1.,-1.,-1.),(1.,-1.,1.))),data.face_bounding_box(0));
+        assert_eq!(Some(((-1.,1.,-1.),(1.,1.,1.))),data.face_bounding_box(1));
+        assert_eq!(None,data.face_bounding_box(12));
+    }
+
+    #[test]
+    fn estimated_scale() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(2.,data.estimated_scale());
+        assert_eq!(Unit::Meters,data.suggest_unit());
+        data.map_vertices(|(x,y,z,w)| (x*1000.,y*1000.,z*1000.,w));
+        assert_eq!(Unit::Millimeters,data.suggest_unit());
+        assert_eq!(0.,ObjData::new().estimated_scale());
+    }
+
+    #[test]
+    fn face_uv_area() {
+        let obj_str =
+        r#"v 0 0 0
+        v 2 0 0
+        v 2 2 0
+        v 0 2 0
+        vt 0.25 0.25
+        vt 0.75 0.25
+        vt 0.75 0.75
+        vt 0.25 0.75
+        f 1/1 2/2 3/3 4/4
+        f 1/4 2/3 3/2 4/1
+        f 1/1 2/2 3 4/4"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(0.25),data.face_uv_area(0));
+        assert_eq!(Some(0.25),data.face_uv_area(1));
+        assert_eq!(None,data.face_uv_area(2));
+        assert_eq!(None,data.face_uv_area(3));
+    }
+
+    #[test]
+    fn face_area_normals() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let area_normals = data.face_area_normals();
+        assert_eq!(12,area_normals.len());
+        for (i,&(area,n)) in area_normals.iter().enumerate() {
+            // Each triangle is half of a side of 2 by 2
+            assert!((area-2.).abs() < 1e-6);
+            assert!((length(n)-1.).abs() < 1e-6);
+            assert_eq!(2,[n.0,n.1,n.2].iter().filter(|&&x| x == 0.).count());
+            assert!((area-data.face_area(i)).abs() < 1e-6);
+            assert_eq!(data.face_normal(i),n);
+        }
+    }
+
+    #[test]
+    fn area_vector() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert!(length(data.area_vector()) < 1e-6);
+
+        // Opening the cube leaves the opposite of the area vector of the missing triangle
+        let normal = data.face_normal(0);
+        data.faces.remove(0);
+        assert!(length(add(data.area_vector(),scale(normal,2.))) < 1e-6);
+    }
+
+    #[test]
+    fn shell_volumes() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        let n = data.vertices.len();
+        for v in 0..n {
+            let (x,y,z,w) = data.vertices[v];
+            data.vertices.push((x+5.,y,z,w));
+        }
+        let faces : Vec<Face> = data.faces.iter().map(|f| f.iter().map(|c| (c.0+n,c.1,c.2)).collect()).collect();
+        data.faces.extend(faces);
+        let volumes = data.shell_volumes();
+        assert_eq!(2,volumes.len());
+        for v in volumes {
+            assert!((v - 8.).abs() < 1e-5);
+        }
+
+        // An inner void wound inwards
+        for v in n..2*n {
+            let (x,y,z,w) = data.vertices[v];
+            data.vertices[v] = ((x-5.)*0.5,y*0.5,z*0.5,w);
+        }
+        for f in &mut data.faces[12..] {
+            f.reverse();
+        }
+        let volumes = data.shell_volumes();
+        assert!((volumes[0] - 8.).abs() < 1e-5);
+        assert!((volumes[1] + 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn largest_face() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 10 0 0
+        v 10 10 0
+        v 0 10 0
+        f 1 2 3 4
+        f 1 2 3
+        f 1 5 6 7
+        f 2 3 4 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(2),data.largest_face());
+        assert_eq!(None,ObjData::new().largest_face());
+
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(0),data.largest_face());
+    }
+
+    #[test]
+    fn best_fit_plane() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        for i in 0..data.faces.len() {
+            let n = data.face_normal(i);
+            let pair : Vec<usize> = (0..data.faces.len()).filter(|&j| data.face_normal(j) == n).collect();
+            assert_eq!(2,pair.len());
+            let (normal,d) = data.best_fit_plane(&pair).unwrap();
+            assert!(length(sub(normal,n)) < 1e-5);
+            assert!((d - 1.).abs() < 1e-5);
+        }
+        assert_eq!(None,data.best_fit_plane(&[]));
+        assert_eq!(None,data.best_fit_plane(&[42]));
+
+        let obj_str =
+        r#"v 0 0 0.1
+        v 2 0 -0.1
+        v 2 2 0.1
+        v 0 2 -0.1
+        v 0 0 0
+        v 1 1 1
+        v 2 2 2
+        f 1 2 3 4
+        f 5 6 7"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let (n,d) = data.best_fit_plane(&[0]).unwrap();
+        assert!(length(sub(n,(0.,0.,1.))) < 1e-5);
+        assert!(d.abs() < 1e-5);
+        assert_eq!(None,data.best_fit_plane(&[1]));
+    }
+
+    #[test]
+    fn edge_length_stats() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let (min,max,mean) = data.edge_length_stats().unwrap();
+        assert_eq!(2.,min);
+        assert!((max - 8f32.sqrt()).abs() < 1e-6);
+        // 12 sides and 6 diagonals
+        assert!((mean - (12.*2. + 6.*8f32.sqrt()) / 18.).abs() < 1e-6);
+        assert_eq!(None,ObjData::new().edge_length_stats());
+    }
+
+    #[test]
+    fn total_edge_length() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        // 12 sides and 6 diagonals
+        assert!((data.total_edge_length() - (12.*2. + 6.*8f32.sqrt())).abs() < 1e-4);
+        assert_eq!(0.,ObjData::new().total_edge_length());
+    }
+
+    #[test]
+    fn bounding_cylinder() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        for &axis in [Axis::Yup, Axis::Zup].iter() {
+            let (height,radius,center) = data.bounding_cylinder(axis).unwrap();
+            assert_eq!(2.,height);
+            assert!((radius - 2f32.sqrt()).abs() < 1e-6);
+            assert!(center.0.abs() < 1e-6 && center.1.abs() < 1e-6);
+        }
+        assert_eq!(None,ObjData::new().bounding_cylinder(Axis::Yup));
+
+        let obj_str =
+        r#"v 0 0 0
+        v 4 1 0
+        v 2 2 1
+        v 1 3 0.5
+        v 2 5 0
+        v 2 7 0"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let (height,radius,center) = data.bounding_cylinder(Axis::Zup).unwrap();
+        assert_eq!(1.,height);
+        for v in &data.vertices {
+            let d = ((v.0-center.0)*(v.0-center.0) + (v.1-center.1)*(v.1-center.1)).sqrt();
+            assert!(d <= radius + 1e-5);
+        }
+        // The circle through (0,0), (4,1) and (2,7)
+        assert!((center.0 - 33./26.).abs() < 1e-5 && (center.1 - 89./26.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn faces_with_zero_edges() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 0.001
+        f 1 2 3 4
+        f 1 5 2 3
+        f 1 2 3 1
+        f 1 2 2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![2,3],data.faces_with_zero_edges(1e-6));
+        assert_eq!(vec![1,2,3],data.faces_with_zero_edges(0.01));
+    }
+
+    #[test]
+    fn find_collinear_faces() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 2 0 0
+        v 0.5 0.0001 0
+        f 1 2 3 4
+        f 1 2 5
+        f 1 6 2 3
+        f 1 2 2
+        f 4 3 2 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![1],data.find_collinear_faces(1e-6));
+        assert_eq!(vec![1,2],data.find_collinear_faces(1e-3));
+        assert_eq!(vec![3],data.faces_with_zero_edges(1e-6));
+    }
+
+    #[test]
+    fn silhouette_edges() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let edges = data.silhouette_edges((1.,1.,1.));
+        assert_eq!(6,edges.len());
+        // The silhouette along the diagonal avoids the two corners on it
+        let corners : Vec<usize> = (0..data.vertices.len()).filter(|&v| {
+            let p = data.position(v);
+            p.0 == p.1 && p.1 == p.2
+        }).collect();
+        assert_eq!(2,corners.len());
+        for &(a,b) in &edges {
+            assert!(!corners.contains(&a) && !corners.contains(&b));
+        }
+        let mut degrees = vec![0; data.vertices.len()];
+        for &(a,b) in &edges {
+            degrees[a] += 1;
+            degrees[b] += 1;
+        }
+        assert!(degrees.iter().all(|&d| d == 0 || d == 2));
+        assert_eq!(edges,data.silhouette_edges((-1.,-1.,-1.)));
+    }
+
+    #[test]
+    fn contains_point() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.contains_point((0.,0.,0.)));
+        assert!(data.contains_point((0.5,-0.3,0.9)));
+        assert!(data.contains_point((1.,0.,0.)));
+        assert!(!data.contains_point((10.,0.,0.)));
+        assert!(!data.contains_point((-10.,0.,0.)));
+        assert!(!data.contains_point((0.,2.,0.)));
+    }
+
+    #[test]
+    fn winding_number() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert!((data.winding_number((0.5,-0.3,0.9)) - 1.).abs() < 1e-5);
+        assert!(data.winding_number((0.,2.,0.)).abs() < 1e-5);
+
+        // Open the cube by removing a triangle of its bottom
+        data.faces.remove(0);
+        assert!((data.winding_number((0.,0.,0.)) - 11./12.).abs() < 1e-5);
+        assert!(data.winding_number((0.,0.5,0.)) > 0.9);
+        assert!(data.winding_number((0.,-1.5,0.)) < 0.5);
+        assert!(data.winding_number((10.,10.,10.)).abs() < 0.01);
+
+        data.flip_winding();
+        assert!((data.winding_number((0.,0.,0.)) + 11./12.).abs() < 1e-5);
+    }
+}
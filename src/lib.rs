@@ -1,8 +1,45 @@
+// The API represents vectors and boxes as plain tuples.
+#![allow(clippy::type_complexity)]
+
+#[cfg(feature = "zip")]
+extern crate zip;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+
 mod obj;
+mod geometry;
+mod topology;
+mod buffers;
+mod mesh;
+mod edit;
+mod bvh;
+mod material;
+#[cfg(feature = "zip")]
+mod archive;
+#[cfg(feature = "mmap")]
+mod mmap;
+pub mod prelude;
 pub use obj::LoadingError;
+pub use obj::ParseDetails;
+pub use obj::LoadOptions;
+pub use obj::FaceParseMode;
+pub use obj::WriteOptions;
+pub use obj::IndexOffsets;
+pub use obj::ElementCounts;
 pub use obj::ObjData;
 pub use obj::Object;
 pub use obj::Group;
+pub use obj::PrimitiveContext;
+pub use mesh::Mesh;
+pub use buffers::SplitBuffers;
+pub use material::Material;
+pub use geometry::Unit;
+pub use bvh::Bvh;
+pub use bvh::Hit;
+pub use edit::Anchor;
+pub use edit::Axis;
+pub use edit::Plane;
+pub use edit::Transform;
 
 #[cfg(test)]
 mod test;
@@ -0,0 +1,73 @@
+use obj::ObjData;
+
+/// Color of the faces without a known material in `face_colors`.
+const DEFAULT_COLOR : (f32,f32,f32) = (0.5,0.5,0.5);
+
+/// A material, as declared by `newmtl` in a material library.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Material {
+    /// Name used by `usemtl` to refer to the material.
+    pub name : String,
+    /// Diffuse color `Kd` as `(r,g,b)`.
+    pub diffuse : (f32,f32,f32),
+}
+
+impl ObjData {
+    /// Diffuse color of the material of each face, looked up by name in `materials`.
+    ///
+    /// Faces without a material, or whose material is missing from `materials`, are gray.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{Material, ObjData};
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let red = Material { name : String::from("red"), diffuse : (1.,0.,0.) };
+    /// assert_eq!(data.face_colors(&[red]), vec![(0.5,0.5,0.5), (1.,0.,0.)]);
+    /// ```
+    pub fn face_colors(&self, materials : &[Material]) -> Vec<(f32,f32,f32)> {
+        (0..self.faces.len()).map(|i| {
+            self.face_material(i)
+                .and_then(|m| materials.iter().find(|mat| mat.name == self.materials[m]))
+                .map_or(DEFAULT_COLOR, |mat| mat.diffuse)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use obj::*;
+    use super::*;
+
+    #[test]
+    fn face_colors() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        usemtl red
+        f 1 2 3
+        usemtl blue
+        f 1 2 3
+        usemtl unknown
+        f 1 2 3
+        usemtl
+        f 1 2 3
+        usemtl red
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let materials = vec![
+            Material { name : String::from("blue"), diffuse : (0.,0.,1.) },
+            Material { name : String::from("red"), diffuse : (1.,0.,0.) },
+        ];
+        let expected = vec![(1.,0.,0.), (0.,0.,1.), DEFAULT_COLOR, DEFAULT_COLOR, (1.,0.,0.)];
+        assert_eq!(expected,data.face_colors(&materials));
+        assert_eq!(vec![DEFAULT_COLOR; 5],data.face_colors(&[]));
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use obj::Corner;
+use obj::ObjData;
+use obj::Object;
+
+/// A triangle mesh independent of the wavefront format.
+///
+/// Every attribute is indexed by the same index: the vertex `i` has the position
+/// `positions[i]`, the normal `normals[i]` and the texture coordinate `texcoords[i]`.
+#[derive(PartialEq, Debug, Default)]
+pub struct Mesh {
+    /// List of vertex positions.
+    pub positions : Vec<[f32;3]>,
+    /// List of vertex normals, empty if the mesh has no normals.
+    pub normals : Vec<[f32;3]>,
+    /// List of vertex texture coordinates `(u,v)`, empty if the mesh has no texture coordinates.
+    pub texcoords : Vec<[f32;2]>,
+    /// List of triangles as vertex indices.
+    pub indices : Vec<[u32;3]>,
+}
+
+impl Mesh {
+    /// Convert the mesh to an `ObjData` with a single unnamed object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::Mesh;
+    ///
+    /// let mesh = Mesh {
+    ///     positions : vec![[0.,0.,0.], [1.,0.,0.], [0.,1.,0.]],
+    ///     normals : Vec::new(),
+    ///     texcoords : Vec::new(),
+    ///     indices : vec![[0,1,2]],
+    /// };
+    /// let data = mesh.to_obj();
+    /// assert_eq!(data.faces, vec![vec![(0,None,None), (1,None,None), (2,None,None)]]);
+    /// ```
+    pub fn to_obj(&self) -> ObjData {
+        let mut data = ObjData::new();
+        data.vertices = self.positions.iter().map(|p| (p[0],p[1],p[2],1.)).collect();
+        data.normals = self.normals.iter().map(|n| (n[0],n[1],n[2])).collect();
+        data.texcoords = self.texcoords.iter().map(|t| (t[0],t[1],0.)).collect();
+        let has_normals = !self.normals.is_empty();
+        let has_texcoords = !self.texcoords.is_empty();
+        for t in &self.indices {
+            let face = t.iter().map(|&i| {
+                let i = i as usize;
+                (i, if has_texcoords {Some(i)} else {None}, if has_normals {Some(i)} else {None})
+            }).collect();
+            data.faces.push(face);
+        }
+        if !data.faces.is_empty() {
+            let mut obj = Object::new(String::new());
+            obj.primitives = (0..data.faces.len()).collect();
+            data.objects.push(obj);
+        }
+        data
+    }
+}
+
+impl ObjData {
+    /// Convert to a triangle `Mesh`.
+    ///
+    /// Faces are triangulated as fans and each distinct `(v,vt,vn)` corner becomes a vertex
+    /// of the mesh. Normals (resp. texture coordinates) are only filled if at least one
+    /// corner has one, missing values being set to zero. The triangles with a vertex index
+    /// past the end of `vertices` are left out, and the texture coordinates and normal
+    /// indices past the end are read as missing. Objects, groups and materials are not kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let mesh = data.to_mesh();
+    /// assert_eq!(mesh.indices.len(), 12);
+    /// assert_eq!(mesh.positions.len(), mesh.normals.len());
+    /// ```
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::default();
+        let has_normals = self.faces.iter().any(|f| f.iter().any(|c| c.2.is_some()));
+        let has_texcoords = self.faces.iter().any(|f| f.iter().any(|c| c.1.is_some()));
+        let mut corners : HashMap<Corner,u32> = HashMap::new();
+        for f in &self.faces {
+            for t in self.fan(f) {
+                let mut triangle = [0;3];
+                for (index,&(v,vt,vn)) in triangle.iter_mut().zip(t.iter()) {
+                    let next = mesh.positions.len() as u32;
+                    *index = *corners.entry((v,vt,vn)).or_insert(next);
+                    if *index == next {
+                        let (x,y,z,_) = self.vertices[v];
+                        mesh.positions.push([x,y,z]);
+                        if has_normals {
+                            let (x,y,z) = vn.and_then(|n| self.normals.get(n).cloned()).unwrap_or((0.,0.,0.));
+                            mesh.normals.push([x,y,z]);
+                        }
+                        if has_texcoords {
+                            let (u,v,_) = vt.and_then(|t| self.texcoords.get(t).cloned()).unwrap_or((0.,0.,0.));
+                            mesh.texcoords.push([u,v]);
+                        }
+                    }
+                }
+                mesh.indices.push(triangle);
+            }
+        }
+        mesh
+    }
+
+    /// Ranges of the triangle indices of each object in the index buffer of `to_mesh`,
+    /// flattened to 3 indices per triangle, so that each object can be drawn by its own call
+    /// into the shared buffer.
+    ///
+    /// The ranges follow the faces in order and cover the whole buffer without overlapping.
+    /// An object whose faces are not consecutive gets one range per run of faces, and the
+    /// faces belonging to no object are reported with an empty name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\no quad\nf 1 2 3 4\no tri\nf 1 2 3";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.object_index_ranges(), vec![(String::from("quad"), 0..6), (String::from("tri"), 6..9)]);
+    /// ```
+    pub fn object_index_ranges(&self) -> Vec<(String,Range<usize>)> {
+        let mut owners : Vec<Option<usize>> = vec![None; self.faces.len()];
+        for (o,obj) in self.objects.iter().enumerate().rev() {
+            for &i in &obj.primitives {
+                if i < owners.len() {
+                    owners[i] = Some(o);
+                }
+            }
+        }
+        let mut ranges : Vec<(Option<usize>,Range<usize>)> = Vec::new();
+        let mut start = 0;
+        for (f,&owner) in self.faces.iter().zip(&owners) {
+            let end = start + 3*self.fan(f).count();
+            match ranges.last_mut() {
+                Some(last) if last.0 == owner => last.1.end = end,
+                _ => ranges.push((owner,start..end)),
+            }
+            start = end;
+        }
+        ranges.into_iter().filter(|r| !r.1.is_empty()).map(|(o,r)| {
+            (o.map_or_else(String::new, |o| self.objects[o].name.clone()),r)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use obj::*;
+
+    #[test]
+    fn obj_mesh_obj() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let mesh = data.to_mesh();
+        assert_eq!(12,mesh.indices.len());
+        assert_eq!(24,mesh.positions.len());
+        assert_eq!(24,mesh.normals.len());
+        assert!(mesh.texcoords.is_empty());
+
+        let reload = mesh.to_obj();
+        assert_eq!(data.faces.len(),reload.faces.len());
+        for (f,r) in data.faces.iter().zip(reload.faces.iter()) {
+            assert_eq!(f.len(),r.len());
+            for (&(v,_,vn),&(rv,rvt,rvn)) in f.iter().zip(r.iter()) {
+                assert_eq!(data.vertices[v],reload.vertices[rv]);
+                assert_eq!(data.normals[vn.unwrap()],reload.normals[rvn.unwrap()]);
+                assert_eq!(None,rvt);
+            }
+        }
+        assert_eq!(vec![Object {
+            name : String::new(),
+            primitives : (0..12).collect()
+        }],reload.objects);
+        assert_eq!(mesh,reload.to_mesh());
+    }
+
+    #[test]
+    fn object_index_ranges() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        f 1 2 5
+        o quad
+        f 1 2 3 4
+        f 1 2 5
+        o fan
+        f 1 2 3 4 5
+        o empty
+        o tri
+        f 2 3 5"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let ranges = data.object_index_ranges();
+        let names : Vec<&str> = ranges.iter().map(|r| r.0.as_str()).collect();
+        assert_eq!(vec!["","quad","fan","tri"],names);
+        let indices = data.to_mesh().indices.len()*3;
+        let mut next = 0;
+        for (_,r) in &ranges {
+            assert_eq!(next,r.start);
+            assert!(r.end > r.start);
+            next = r.end;
+        }
+        assert_eq!(indices,next);
+        assert_eq!(3..12,ranges[1].1);
+    }
+
+    #[test]
+    fn indices_past_the_end() {
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nvn 0 0 1\nf 1 2 4\nf 1//1 2//2 3//1\no quad\nf 1 2 3 4".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let mesh = data.to_mesh();
+        assert_eq!(vec![[0,1,2],[3,4,5]],mesh.indices);
+        assert_eq!(vec![[0.,0.,1.],[0.,0.,0.],[0.,0.,1.],[0.,0.,0.],[0.,0.,0.],[0.,0.,0.]],mesh.normals);
+        assert_eq!(vec![(String::new(),0..3),("quad".to_string(),3..6)],data.object_index_ranges());
+    }
+}
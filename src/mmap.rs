@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use memmap2::Mmap;
+use obj::{LoadOptions, LoadingError, ObjData};
+
+impl ObjData {
+    /// Load an `ObjData` from the file at `path` mapped in memory, remembering its directory
+    /// in `base_dir`.
+    ///
+    /// The lines are parsed in place from the mapped bytes, without being copied. The file
+    /// must not be truncated or modified by another process while loading, which may crash
+    /// the program or give inconsistent data.
+    pub fn load_mmap<P : AsRef<Path>>(path : P) -> Result<ObjData,LoadingError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // The caller is responsible for the file not being modified while mapped
+        let mapping = unsafe { Mmap::map(&file)? };
+        let mut data = ObjData::load_bytes(&mapping, &LoadOptions::default())?;
+        data.base_dir = Some(path.parent().map_or_else(PathBuf::new, Path::to_path_buf));
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::path::PathBuf;
+    use obj::*;
+
+    #[test]
+    fn load_mmap() {
+        let data = ObjData::load_mmap("cube.obj").ok().unwrap();
+        let expected = ObjData::load(&mut BufReader::new(File::open("cube.obj").unwrap())).ok().unwrap();
+        assert_eq!(expected.vertices,data.vertices);
+        assert_eq!(expected.normals,data.normals);
+        assert_eq!(expected.faces,data.faces);
+        assert_eq!(expected.objects,data.objects);
+        assert_eq!(expected.groups,data.groups);
+        assert_eq!(Some(PathBuf::new()),data.base_dir);
+
+        let path = env::temp_dir().join("lwobj_load_mmap_empty.obj");
+        File::create(&path).unwrap();
+        let empty = ObjData::load_mmap(&path).ok().unwrap();
+        assert!(empty.vertices.is_empty() && empty.faces.is_empty());
+        fs::remove_file(&path).unwrap();
+        assert!(ObjData::load_mmap("missing.obj").is_err());
+    }
+
+    #[test]
+    fn load_bytes() {
+        let obj_str = "v 0 0 0\r\nv 1 0 0\n\nv 0 1 0\nf 1 2 3";
+        let data = ObjData::load_bytes(obj_str.as_bytes(), &LoadOptions::default()).ok().unwrap();
+        let expected = ObjData::load(&mut BufReader::new(obj_str.as_bytes())).ok().unwrap();
+        assert_eq!(expected.vertices,data.vertices);
+        assert_eq!(expected.faces,data.faces);
+        assert_eq!(expected.face_lines,data.face_lines);
+
+        let options = LoadOptions { max_line_length : Some(7), ..Default::default() };
+        match ObjData::load_bytes(b"v 0 0 0\nv 10 0 0", &options) {
+            Err(LoadingError::LineTooLong(1)) => {},
+            _ => panic!(),
+        }
+        assert!(ObjData::load_bytes(b"o Caf\xe9", &LoadOptions::default()).is_err());
+        let options = LoadOptions { lossy : true, ..Default::default() };
+        let data = ObjData::load_bytes(b"o Caf\xe9", &options).ok().unwrap();
+        assert_eq!("Caf\u{FFFD}",data.objects[0].name);
+    }
+}
@@ -1,14 +1,46 @@
 use std::io::BufRead;
-use std::io::Write;
 use std::io;
 use std::str::FromStr;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// A face corner as a `(v,vt,vn)` index tuple.
+pub type Corner = (usize,Option<usize>,Option<usize>);
+
+/// A face as a list of corners.
+pub type Face = Vec<Corner>;
+
+/// Token of a statement which could not be parsed, reported by `LoadingError::Parse`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ParseDetails {
+    /// Index of the token among the arguments of the statement, from `0`.
+    pub token : usize,
+    /// The token itself.
+    pub text : String,
+    /// Why it could not be parsed.
+    pub message : String,
+}
 
 #[derive(Debug)]
 pub enum LoadingError {
     InvalidLine(usize),
     WrongNumberOfArguments(usize),
-    Parse(usize),
+    Parse(usize,ParseDetails),
+    /// Extra arguments follow a complete statement, reported in strict mode only.
+    TrailingData(usize),
+    /// A face corner has no vertex index, such as `/2/3` or a lone `/`.
+    MalformedFace(usize),
+    /// A coordinate is not finite, such as `nan`, `inf` or `1e400`, reported with
+    /// `strict_finite` only.
+    NonFinite(usize),
+    /// A line is longer than `max_line_length`, reported with that option only.
+    LineTooLong(usize),
+    /// Two meshes expected to share their topology have different numbers of vertices,
+    /// given in the order of the arguments, such as in `lerp`.
+    TopologyMismatch(usize,usize),
     Io(io::Error),
 }
 
@@ -24,6 +56,233 @@ pub struct Object {
     pub primitives : Vec<usize>
 }
 
+/// Where a line or a point was declared in the loaded file, so that `write` keeps it among
+/// the faces of its object and in its groups.
+#[derive(PartialEq, Clone, Debug)]
+pub struct PrimitiveContext {
+    /// Number of faces declared before it.
+    pub faces_before : usize,
+    /// Number of lines and points declared before it.
+    pub rank : usize,
+    /// Index in `objects` of the object it belongs to, if any.
+    pub object : Option<usize>,
+    /// Indices in `groups` of the groups it belongs to.
+    pub groups : Vec<usize>,
+}
+
+/// Statements describing free-form curves and surfaces.
+const FREE_FORM_STATEMENTS : [&str; 15] = ["vp", "cstype", "deg", "bmat", "step", "curv", "curv2",
+    "surf", "parm", "trim", "hole", "scrv", "sp", "end", "con"];
+
+/// How the corners `v/vt/vn` of the `f` statements are split.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FaceParseMode {
+    /// Each corner is a single whitespace-separated token, so `f 1 / 2 / 3` fails with
+    /// `MalformedFace`.
+    Strict,
+    /// Whitespace around the slashes is ignored, so `f 1 / 2 / 3 4/5/6 7 /8/ 9` reads
+    /// as `f 1/2/3 4/5/6 7/8/9`.
+    Lenient,
+}
+
+impl Default for FaceParseMode {
+    fn default() -> FaceParseMode {
+        FaceParseMode::Strict
+    }
+}
+
+/// Options controlling how an `ObjData` is loaded.
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// Skip the statements that are recognized but not supported, such as free-form
+    /// curves and surfaces (`cstype`, `parm`, `trim`, `end`...), instead of failing
+    /// with `InvalidLine`.
+    pub lenient : bool,
+    /// Replace the invalid UTF-8 sequences with `U+FFFD` instead of failing.
+    pub lossy : bool,
+    /// Fail with `TrailingData` when a `v`, `vn` or `vt` statement has valid coordinates
+    /// followed by extra arguments, such as `v 1 2 3 extra` or `v 1 2 3 4 5`, instead of
+    /// `Parse` or `WrongNumberOfArguments`.
+    pub strict : bool,
+    /// How the corners of the faces are split, `Strict` by default.
+    pub face_parse_mode : FaceParseMode,
+    /// Add the faces of a `g` statement to the groups of the same name declared before it,
+    /// rather than to new groups. Set by default.
+    pub merge_groups_by_name : bool,
+    /// Stop reading once this many faces have been read, leaving the rest of the input unread.
+    pub max_faces : Option<usize>,
+    /// Fail with `Parse` when a face refers to a vertex, texture coordinates or normal not
+    /// declared before it, such as `f 5 6 7` before the fifth vertex.
+    pub check_references : bool,
+    /// Read a comma in the coordinates of `v`, `vn` and `vt` as a decimal separator, such as
+    /// `v 1,5 2,0 3,0`, which some exporters write following the locale.
+    ///
+    /// This is a compatibility shim for such non-conforming files: a comma is only replaced
+    /// in the tokens holding a single one and no dot. Leading `+` signs are always accepted.
+    pub decimal_comma : bool,
+    /// Drop the objects left without faces, lines or points, such as a trailing `o` statement,
+    /// as done by `remove_empty_objects`.
+    pub remove_empty_objects : bool,
+    /// Fail with `NonFinite` when a coordinate of a `v`, `vn` or `vt` statement is NaN or
+    /// infinite, which would otherwise be loaded as is.
+    pub strict_finite : bool,
+    /// Fail with `LineTooLong` on a line longer than this many bytes, as soon as the limit
+    /// is reached, to bound the memory used by untrusted inputs.
+    pub max_line_length : Option<usize>,
+    /// Resolve the face indices once all the `v`, `vn` and `vt` statements are read, so that
+    /// a face may refer to elements declared after it, such as `f 1 2 3` at the top of the
+    /// file, while still failing with `Parse` on an index beyond all the elements read.
+    pub two_pass : bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            lenient : false,
+            lossy : false,
+            strict : false,
+            face_parse_mode : FaceParseMode::Strict,
+            merge_groups_by_name : true,
+            max_faces : None,
+            check_references : false,
+            decimal_comma : false,
+            remove_empty_objects : false,
+            strict_finite : false,
+            max_line_length : None,
+            two_pass : false,
+        }
+    }
+}
+
+/// Number of statements of each kind in a wavefront file.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct ElementCounts {
+    /// Number of `v` statements.
+    pub vertices : usize,
+    /// Number of `vn` statements.
+    pub normals : usize,
+    /// Number of `vt` statements.
+    pub texcoords : usize,
+    /// Number of `f` statements.
+    pub faces : usize,
+    /// Number of `l` statements.
+    pub lines : usize,
+    /// Number of `p` statements.
+    pub points : usize,
+    /// Number of `o` statements.
+    pub objects : usize,
+    /// Number of `g` statements.
+    pub groups : usize,
+}
+
+/// Numbers of elements already written in an output, by which `write_append` shifts the
+/// face indices.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct IndexOffsets {
+    /// Number of `v` statements already written.
+    pub vertices : usize,
+    /// Number of `vn` statements already written.
+    pub normals : usize,
+    /// Number of `vt` statements already written.
+    pub texcoords : usize,
+}
+
+/// Options controlling how an `ObjData` is written.
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    /// Start the output with a `# Generated by lwobj` comment.
+    pub header : bool,
+    /// Comment written at the top of the output, after the header, one `#` line per line.
+    pub comment : Option<String>,
+    /// Write the vertices whose weight is exactly `1` as `v x y z`, the other ones keeping
+    /// their fourth component.
+    pub omit_unit_weight : bool,
+    /// Write the face corners without normal with the slashes of the missing indices, as
+    /// `9/4/` and `9//`, rather than `9/4` and `9`. Set by default.
+    ///
+    /// It only applies to the corners without recorded slashes: with `preserve_slashes`,
+    /// the corners of a loaded mesh keep the slashes they were read with.
+    pub trailing_slash : bool,
+    /// Write the faces of each object sorted by groups, in the order of their first face,
+    /// so that each set of groups is declared once per object. This changes the order of the
+    /// faces in the output, but not in memory.
+    pub sort_by_group : bool,
+    /// Write the corners recorded in `face_slashes` with the slashes they were loaded with,
+    /// taking precedence over `trailing_slash` and over the faces written without slashes.
+    /// Set by default.
+    pub preserve_slashes : bool,
+    /// Number of decimals of the coordinates, the shortest representation reading back as
+    /// the same value if `None`.
+    pub precision : Option<usize>,
+    /// Write the coordinates equal to `-0` once formatted as `0`.
+    pub normalize_zero : bool,
+    /// Write the face corners having a normal but no texture coordinates with a `vt 0 0`
+    /// added after the texture coordinates, as `9/5/3` rather than `9//3`, for the importers
+    /// rejecting the empty middle index.
+    pub explicit_default_texcoord : bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            header : false,
+            comment : None,
+            omit_unit_weight : false,
+            trailing_slash : true,
+            sort_by_group : false,
+            preserve_slashes : true,
+            precision : None,
+            normalize_zero : false,
+            explicit_default_texcoord : false,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Options writing the same mesh identically however it was built or loaded.
+    ///
+    /// Coordinates have 6 decimals without negative zeros, unit weights are omitted and the
+    /// face corners have no slash beyond their last index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::{BufReader, BufWriter};
+    /// use std::str;
+    /// use lwobj::{ObjData, WriteOptions};
+    ///
+    /// let mut input = BufReader::new("v -0 0.5 1\nv 1 0 0\nv 0 1 0\nf 1// 2// 3//".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let mut output = BufWriter::new(Vec::new());
+    /// data.write_with_options(&mut output, &WriteOptions::canonical()).ok().unwrap();
+    /// let bytes = output.into_inner().unwrap();
+    /// assert!(str::from_utf8(&bytes).unwrap().starts_with("v 0.000000 0.500000 1.000000\n"));
+    /// assert!(str::from_utf8(&bytes).unwrap().ends_with("f 1 2 3\n"));
+    /// ```
+    pub fn canonical() -> WriteOptions {
+        WriteOptions {
+            omit_unit_weight : true,
+            trailing_slash : false,
+            preserve_slashes : false,
+            precision : Some(6),
+            normalize_zero : true,
+            ..Default::default()
+        }
+    }
+
+    /// Format the coordinate `x` according to `precision` and `normalize_zero`.
+    fn format(&self, x : f32) -> String {
+        let s = match self.precision {
+            Some(p) => format!("{:.*}",p,x),
+            None => x.to_string(),
+        };
+        if self.normalize_zero && s.starts_with('-') && s[1..].chars().all(|c| c == '0' || c == '.') {
+            return s[1..].to_string();
+        }
+        s
+    }
+}
+
 /// A struct containing all data store by wavefront.
 pub struct ObjData {
     /// List of vertices `(x,y,z,w)`.
@@ -40,11 +299,37 @@ pub struct ObjData {
     /// v is the index of vertex.
     /// vt is the index of its texture coordinate if it has one.
     /// vn is the index of its normal vector if it has one.
-    pub faces : Vec<Vec<(usize,Option<usize>,Option<usize>)>>,
+    pub faces : Vec<Face>,
     /// List of Objects
     pub objects : Vec<Object>,
-    /// List of groups
-    pub groups : Vec<Group>
+    /// List of groups. The faces in none of them, before the first `g` or after a bare `g`,
+    /// are in the default group, see `default_group`.
+    pub groups : Vec<Group>,
+    /// List of material libraries referenced by `mtllib`.
+    pub material_libs : Vec<String>,
+    /// List of material names referenced by `usemtl`.
+    pub materials : Vec<String>,
+    /// Index in `materials` of the material of each face, if it has one.
+    pub face_materials : Vec<Option<usize>>,
+    /// Smoothing group of each face, `0` meaning no smoothing.
+    pub smoothing_groups : Vec<u32>,
+    /// Number of slashes of each corner of each face as loaded, from `0` for `4` to `2` for
+    /// `4//`, so that `write` reproduces them.
+    pub face_slashes : Vec<Vec<u8>>,
+    /// One-based line of the `f` statement of each face in the loaded file.
+    pub face_lines : Vec<usize>,
+    /// List of polylines declared by `l`.
+    /// Each line is a list of `(v,vt)`, with the index of a vertex and of its texture
+    /// coordinate if it has one.
+    pub lines : Vec<Vec<(usize,Option<usize>)>>,
+    /// List of points declared by `p`, each one being a list of vertex indices.
+    pub points : Vec<Vec<usize>>,
+    /// Context of each line of `lines` in the loaded file.
+    pub line_contexts : Vec<PrimitiveContext>,
+    /// Context of each point of `points` in the loaded file.
+    pub point_contexts : Vec<PrimitiveContext>,
+    /// Directory of the file loaded by `load_from_path`, against which `material_libs` are resolved.
+    pub base_dir : Option<PathBuf>,
 }
 
 impl From<io::Error> for LoadingError {
@@ -53,16 +338,216 @@ impl From<io::Error> for LoadingError {
     }
 }
 
-fn parse<T : FromStr>(it : Vec<&str>, nb : usize) -> Result<Vec<T>, LoadingError> {
+const ELEMENT_KINDS : [&str; 3] = ["vertices", "texture coordinates", "normals"];
+
+/// `LoadingError::Parse` for the token `text` at index `token` of the line `nb`.
+fn parse_error<M : ToString>(nb : usize, token : usize, text : &str, message : M) -> LoadingError {
+    LoadingError::Parse(nb, ParseDetails {token, text : String::from(text), message : message.to_string()})
+}
+
+fn parse<T : FromStr>(it : Vec<&str>, nb : usize) -> Result<Vec<T>, LoadingError> where T::Err : ToString {
     let mut vec : Vec<T> = Vec::new();
-    for s in it {
+    for (i,s) in it.into_iter().enumerate() {
         let val = match s.parse::<T>() {
             Ok(v) => v,
-            Err(_) => return Err(LoadingError::Parse(nb)),
+            Err(e) => return Err(parse_error(nb, i, s, e)),
         };
         vec.push(val);
     }
-    return Ok(vec);
+    Ok(vec)
+}
+
+/// Replace the comma of `arg` by a dot if it is its single decimal separator.
+fn decimal_comma(arg : &str) -> String {
+    if arg.matches(',').count() == 1 && !arg.contains('.') {
+        arg.replace(',', ".")
+    } else {
+        String::from(arg)
+    }
+}
+
+/// Test if `args` starts with at least `min` numbers but has other arguments or more than `max`.
+fn has_trailing_data(args : &[&str], min : usize, max : usize) -> bool {
+    let numbers = args.iter().take_while(|a| a.parse::<f32>().is_ok()).count();
+    numbers >= min && (numbers < args.len() || args.len() > max)
+}
+
+/// Convert a one-based `index` to a zero-based one.
+///
+/// The elements of the input start at `first`, after the ones loaded before it. A negative
+/// index is relative, `-1` referring to the last of the `count` elements read so far.
+fn resolve_index(index : &str, first : usize, count : usize) -> Result<usize,String> {
+    let i = index.parse::<isize>().map_err(|e| e.to_string())?;
+    if i > 0 {
+        Ok(first + i as usize - 1)
+    } else if i < 0 && i.unsigned_abs() <= count - first {
+        Ok(count - i.unsigned_abs())
+    } else if i == 0 {
+        Err(String::from("indices start at 1"))
+    } else {
+        Err(format!("relative index beyond the {} elements read so far", count - first))
+    }
+}
+
+/// Read the line `nb` of `input` and append it to `buf`.
+///
+/// If `lossy` is set, invalid UTF-8 sequences are replaced, `bytes` being used as buffer.
+/// A line longer than `max_length` bytes, without its line feed, fails with `LineTooLong`
+/// as soon as the limit is reached, without reading the rest of it.
+fn read_line<R : BufRead>(input : &mut R, bytes : &mut Vec<u8>, buf : &mut String, lossy : bool,
+                          max_length : Option<usize>, nb : usize) -> Result<usize,LoadingError> {
+    let max = match max_length {
+        Some(max) => max,
+        None if !lossy => return Ok(input.read_line(buf)?),
+        None => usize::MAX,
+    };
+    bytes.clear();
+    loop {
+        let available = input.fill_buf()?;
+        if available.is_empty() {
+            break;
+        }
+        let (used,end) = match available.iter().position(|&b| b == b'\n') {
+            Some(p) => (p+1,true),
+            None => (available.len(),false),
+        };
+        if bytes.len() + used - end as usize > max {
+            return Err(LoadingError::LineTooLong(nb));
+        }
+        bytes.extend_from_slice(&available[..used]);
+        input.consume(used);
+        if end {
+            break;
+        }
+    }
+    if lossy {
+        buf.push_str(&String::from_utf8_lossy(bytes));
+    } else {
+        let line = ::std::str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        buf.push_str(line);
+    }
+    Ok(bytes.len())
+}
+
+/// A source of lines for the loader.
+trait Lines {
+    /// The line `nb`, with its line feed if any, or `None` at the end of the input.
+    fn next_line(&mut self, options : &LoadOptions, nb : usize) -> Result<Option<&str>,LoadingError>;
+}
+
+/// The lines of a `BufRead`, read one at a time into a buffer.
+struct ReaderLines<'a, R : 'a> {
+    input : &'a mut R,
+    bytes : Vec<u8>,
+    buf : String,
+}
+
+impl<'a, R : BufRead> Lines for ReaderLines<'a, R> {
+    fn next_line(&mut self, options : &LoadOptions, nb : usize) -> Result<Option<&str>,LoadingError> {
+        self.buf.clear();
+        if read_line(self.input, &mut self.bytes, &mut self.buf, options.lossy, options.max_line_length, nb)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(&self.buf))
+    }
+}
+
+/// The lines of a string, borrowed without being copied.
+#[cfg(feature = "mmap")]
+struct StrLines<'a> {
+    rest : &'a str,
+}
+
+#[cfg(feature = "mmap")]
+impl<'a> Lines for StrLines<'a> {
+    fn next_line(&mut self, options : &LoadOptions, nb : usize) -> Result<Option<&str>,LoadingError> {
+        if self.rest.is_empty() {
+            return Ok(None);
+        }
+        let (line,rest) = self.rest.split_at(self.rest.find('\n').map_or(self.rest.len(), |p| p+1));
+        if options.max_line_length.map_or(false, |max| line.trim_end_matches('\n').len() > max) {
+            return Err(LoadingError::LineTooLong(nb));
+        }
+        self.rest = rest;
+        Ok(Some(line))
+    }
+}
+
+/// The counts announced by the text of a comment, such as `8 vertices, 12 faces` or
+/// `Vertices: 8`, as `"vertices"` or `"faces"` and the count.
+fn count_comment(text : &str) -> Vec<(&'static str,usize)> {
+    let kind = |token : &str| match token.to_lowercase().as_str() {
+        "vertices" | "vertex" | "verts" => Some("vertices"),
+        "faces" | "face" => Some("faces"),
+        _ => None,
+    };
+    let tokens : Vec<&str> = text.split_whitespace().map(|t| t.trim_matches(|c| c == ':' || c == ',' || c == ';'))
+        .filter(|t| !t.is_empty()).collect();
+    let mut counts = Vec::new();
+    let mut i = 0;
+    while i + 1 < tokens.len() {
+        let found = match (tokens[i].parse::<usize>(), tokens[i+1].parse::<usize>()) {
+            (Ok(n),_) => kind(tokens[i+1]).map(|k| (k,n)),
+            (_,Ok(n)) => kind(tokens[i]).map(|k| (k,n)),
+            _ => None,
+        };
+        match found {
+            Some(count) => {
+                counts.push(count);
+                i += 2;
+            },
+            None => i += 1,
+        }
+    }
+    counts
+}
+
+/// Remove the surrounding quotes of a name, if any.
+fn unquote(name : String) -> String {
+    if name.len() >= 2 && name.starts_with('"') && name.ends_with('"') {
+        return String::from(&name[1..name.len()-1]);
+    }
+    name
+}
+
+/// Surround a name with quotes if it contains whitespaces.
+fn quote(name : &str) -> String {
+    if name.contains(char::is_whitespace) {
+        return format!("\"{}\"",name);
+    }
+    String::from(name)
+}
+
+/// Collect the group names of a `g` statement, keeping quoted names with spaces together.
+fn group_names(args : Vec<&str>) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut quoted : Option<String> = None;
+    for arg in args {
+        quoted = match quoted {
+            Some(mut name) => {
+                name += " ";
+                name += arg;
+                if arg.ends_with('"') {
+                    names.push(unquote(name));
+                    None
+                } else {
+                    Some(name)
+                }
+            },
+            None => {
+                if arg.starts_with('"') && (arg.len() == 1 || !arg.ends_with('"')) {
+                    Some(String::from(arg))
+                } else {
+                    names.push(unquote(String::from(arg)));
+                    None
+                }
+            },
+        };
+    }
+    if let Some(name) = quoted {
+        names.push(name);
+    }
+    names
 }
 
 impl Group {
@@ -83,6 +568,30 @@ impl Object {
     }
 }
 
+impl Default for ObjData {
+    fn default() -> ObjData {
+        ObjData::new()
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ObjData {
+    type Error = LoadingError;
+
+    /// Load an `ObjData` from the content of a wavefront file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::try_from(&b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3"[..]).ok().unwrap();
+    /// assert_eq!(data.faces.len(), 1);
+    /// ```
+    fn try_from(bytes : &'a [u8]) -> Result<ObjData,LoadingError> {
+        ObjData::load(&mut io::BufReader::new(io::Cursor::new(bytes)))
+    }
+}
 
 impl ObjData {
     /// Constructs a new empty `ObjData`.
@@ -102,11 +611,60 @@ impl ObjData {
             faces : Vec::new(),
             objects : Vec::new(),
             groups : Vec::new(),
+            material_libs : Vec::new(),
+            materials : Vec::new(),
+            face_materials : Vec::new(),
+            smoothing_groups : Vec::new(),
+            face_slashes : Vec::new(),
+            face_lines : Vec::new(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            line_contexts : Vec::new(),
+            point_contexts : Vec::new(),
+            base_dir : None,
         }
     }
 
+    /// Remove all the elements, keeping the allocated memory for reuse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.clear();
+    /// assert!(data.vertices.is_empty() && data.faces.is_empty() && data.objects.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.normals.clear();
+        self.texcoords.clear();
+        self.faces.clear();
+        self.objects.clear();
+        self.groups.clear();
+        self.material_libs.clear();
+        self.materials.clear();
+        self.face_materials.clear();
+        self.smoothing_groups.clear();
+        self.face_slashes.clear();
+        self.face_lines.clear();
+        self.lines.clear();
+        self.points.clear();
+        self.line_contexts.clear();
+        self.point_contexts.clear();
+        self.base_dir = None;
+    }
+
     /// Load an `ObjData` from a `BufReader`.
     ///
+    /// Face indices are not checked against the elements read so far, so a face may
+    /// reference vertices, texture coordinates or normals declared later in the file.
+    /// Use `LoadOptions::two_pass` to check them once all the elements are read.
+    /// Negative indices are relative to the elements read so far, `-1` being the last one.
+    ///
     /// # Examples
     ///
     /// ```
@@ -119,21 +677,272 @@ impl ObjData {
     /// let data = ObjData::load(&mut input).ok().unwrap();
     /// ```
     pub fn load<R : io::Read>(input : &mut io::BufReader<R>) -> Result<ObjData,LoadingError> {
+        ObjData::load_with_handler(input, |_,_,_| Ok(false))
+    }
+
+    /// Load an `ObjData` from a `BufReader`, delegating unknown statements to `handler`.
+    ///
+    /// The handler receives the identifier, its arguments and the line number.
+    /// It returns `Ok(true)` if it handled the statement, `Ok(false)` to report it as an
+    /// `InvalidLine`, or an error to abort the loading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 1 2 3\nmyext 42".as_bytes());
+    /// let mut ext = Vec::new();
+    /// let data = ObjData::load_with_handler(&mut input, |id, args, _| {
+    ///     if id == "myext" {
+    ///         ext.push(args.join(" "));
+    ///         return Ok(true);
+    ///     }
+    ///     Ok(false)
+    /// }).ok().unwrap();
+    /// assert_eq!(data.vertices.len(), 1);
+    /// assert_eq!(ext, vec!["42"]);
+    /// ```
+    pub fn load_with_handler<R, F>(input : &mut io::BufReader<R>, handler : F) -> Result<ObjData,LoadingError>
+        where R : io::Read, F : FnMut(&str, &[&str], usize) -> Result<bool,LoadingError> {
+        ObjData::load_impl(input, &LoadOptions::default(), handler, |_,_| {})
+    }
+
+    /// Load an `ObjData` from a `BufReader`, passing the comments to `handler`.
+    ///
+    /// The handler receives the text of each comment line, without its `#` and surrounding
+    /// whitespace, and the line number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("# Exported cube\nv 1 2 3".as_bytes());
+    /// let mut comments = Vec::new();
+    /// let data = ObjData::load_with_comment_handler(&mut input, |text, nb| comments.push((String::from(text), nb)))
+    ///     .ok().unwrap();
+    /// assert_eq!(data.vertices.len(), 1);
+    /// assert_eq!(comments, vec![(String::from("Exported cube"), 0)]);
+    /// ```
+    pub fn load_with_comment_handler<R, C>(input : &mut io::BufReader<R>, handler : C) -> Result<ObjData,LoadingError>
+        where R : io::Read, C : FnMut(&str, usize) {
+        ObjData::load_impl(input, &LoadOptions::default(), |_,_,_| Ok(false), handler)
+    }
+
+    /// Load an `ObjData` from a `BufReader` with the given `LoadOptions`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{LoadOptions,ObjData};
+    ///
+    /// let mut input = BufReader::new("v 1 2 3\nparm u 0 1\nend".as_bytes());
+    /// let options = LoadOptions { lenient : true, ..Default::default() };
+    /// let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+    /// assert_eq!(data.vertices.len(), 1);
+    /// ```
+    pub fn load_with_options<R : io::Read>(input : &mut io::BufReader<R>, options : &LoadOptions) -> Result<ObjData,LoadingError> {
+        ObjData::load_impl(input, options, |_,_,_| Ok(false), |_,_| {})
+    }
+
+    /// Load the start of a `BufReader` into an `ObjData`, up to its first `max_faces` faces.
+    ///
+    /// Everything declared before the last face read is loaded, and the rest of the input
+    /// is not read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nv 1 1 0\nf 2 4 3".as_bytes());
+    /// let data = ObjData::load_limited(&mut input, 1).ok().unwrap();
+    /// assert_eq!(data.faces.len(), 1);
+    /// assert_eq!(data.vertices.len(), 3);
+    /// ```
+    pub fn load_limited<R : io::Read>(input : &mut io::BufReader<R>, max_faces : usize) -> Result<ObjData,LoadingError> {
+        let options = LoadOptions { max_faces : Some(max_faces), ..Default::default() };
+        ObjData::load_with_options(input, &options)
+    }
+
+    /// Replace the content of this `ObjData` by the one loaded from a `BufReader`, reusing
+    /// its allocated memory.
+    ///
+    /// On error, the elements read before the failing statement are left in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// for obj_str in ["v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3", "v 1 2 3"].iter() {
+    ///     let mut input = BufReader::new(obj_str.as_bytes());
+    ///     data.load_into(&mut input).ok().unwrap();
+    /// }
+    /// assert_eq!(data.vertices, vec![(1.,2.,3.,1.)]);
+    /// assert!(data.faces.is_empty());
+    /// ```
+    pub fn load_into<R : io::Read>(&mut self, input : &mut io::BufReader<R>) -> Result<(),LoadingError> {
+        self.clear();
+        ObjData::load_into_impl(self, &mut ReaderLines {input, bytes : Vec::new(), buf : String::new()}, &LoadOptions::default(), |_,_,_| Ok(false), |_,_| {})
+    }
+
+    /// Load a `BufReader` after the content of this `ObjData`, so that several files can be
+    /// streamed into a single mesh.
+    ///
+    /// The indices of the loaded faces, lines and points are shifted past the elements already
+    /// there. Groups of the same name are merged, while objects are always new ones.
+    /// On error, the elements read before the failing statement are left in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::new();
+    /// for _ in 0..2 {
+    ///     let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    ///     data.load_extend(&mut input).ok().unwrap();
+    /// }
+    /// assert_eq!(data.vertices.len(), 6);
+    /// assert_eq!(data.faces[1], vec![(3,None,None), (4,None,None), (5,None,None)]);
+    /// ```
+    pub fn load_extend<R : io::Read>(&mut self, input : &mut io::BufReader<R>) -> Result<(),LoadingError> {
+        // Fill the per face data, which may be shorter than the faces, before pushing to it
+        let faces = self.faces.len();
+        let lines = self.face_lines.len() == faces;
+        self.face_materials.resize(faces, None);
+        self.smoothing_groups.resize(faces, 0);
+        self.face_slashes.resize(faces, Vec::new());
+        let contexts = (self.line_contexts.len() == self.lines.len(), self.point_contexts.len() == self.points.len());
+        let result = ObjData::load_into_impl(self, &mut ReaderLines {input, bytes : Vec::new(), buf : String::new()}, &LoadOptions::default(), |_,_,_| Ok(false), |_,_| {});
+        if !lines {
+            self.face_lines.clear();
+        }
+        if !contexts.0 {
+            self.line_contexts.clear();
+        }
+        if !contexts.1 {
+            self.point_contexts.clear();
+        }
+        result
+    }
+
+    /// Load an `ObjData` from a `BufReader`, replacing invalid UTF-8 sequences.
+    ///
+    /// Files using another encoding, such as Latin-1 names, are still loaded, the invalid
+    /// sequences being replaced with `U+FFFD`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new(&b"o Caf\xe9\nv 1 2 3"[..]);
+    /// let data = ObjData::load_lossy(&mut input).ok().unwrap();
+    /// assert_eq!(data.objects[0].name, "Caf\u{FFFD}");
+    /// ```
+    pub fn load_lossy<R : io::Read>(input : &mut io::BufReader<R>) -> Result<ObjData,LoadingError> {
+        let options = LoadOptions { lossy : true, ..Default::default() };
+        ObjData::load_with_options(input, &options)
+    }
+
+    /// Load an `ObjData` from the file at `path`, remembering its directory in `base_dir`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::load_from_path("cube.obj").ok().unwrap();
+    /// assert_eq!(data.vertices.len(), 8);
+    /// ```
+    pub fn load_from_path<P : AsRef<Path>>(path : P) -> Result<ObjData,LoadingError> {
+        let path = path.as_ref();
+        let mut input = io::BufReader::new(File::open(path)?);
+        let mut data = ObjData::load(&mut input)?;
+        data.base_dir = Some(path.parent().map_or_else(PathBuf::new, Path::to_path_buf));
+        Ok(data)
+    }
+
+    /// Paths of the material libraries, relative ones being resolved against `base_dir`.
+    ///
+    /// They are returned unchanged when `base_dir` is `None`.
+    pub fn material_lib_paths(&self) -> Vec<PathBuf> {
+        self.material_libs.iter().map(|lib| match self.base_dir {
+            Some(ref dir) => dir.join(lib),
+            None => PathBuf::from(lib),
+        }).collect()
+    }
+
+    fn load_impl<R, F, C>(input : &mut io::BufReader<R>, options : &LoadOptions, handler : F, comments : C) -> Result<ObjData,LoadingError>
+        where R : io::Read, F : FnMut(&str, &[&str], usize) -> Result<bool,LoadingError>, C : FnMut(&str, usize) {
         let mut data = ObjData::new();
-        let mut buf = String::new();
+        ObjData::load_into_impl(&mut data, &mut ReaderLines {input, bytes : Vec::new(), buf : String::new()}, options, handler, comments)?;
+        Ok(data)
+    }
+
+    /// Load an `ObjData` from bytes held in memory, parsing the lines in place.
+    ///
+    /// The bytes are checked to be UTF-8 at once, or converted if `options.lossy` is set.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn load_bytes(bytes : &[u8], options : &LoadOptions) -> Result<ObjData,LoadingError> {
+        let text = if options.lossy {
+            String::from_utf8_lossy(bytes)
+        } else {
+            ::std::borrow::Cow::Borrowed(::std::str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?)
+        };
+        let mut data = ObjData::new();
+        ObjData::load_into_impl(&mut data, &mut StrLines {rest : &text}, options, |_,_,_| Ok(false), |_,_| {})?;
+        Ok(data)
+    }
+
+    fn load_into_impl<L, F, C>(data : &mut ObjData, lines : &mut L, options : &LoadOptions, mut handler : F, mut comments : C) -> Result<(),LoadingError>
+        where L : Lines, F : FnMut(&str, &[&str], usize) -> Result<bool,LoadingError>, C : FnMut(&str, usize) {
         let mut nb : usize = 0;
+        let first = IndexOffsets {vertices : data.vertices.len(), normals : data.normals.len(), texcoords : data.texcoords.len()};
         let mut actif_groups : Vec<usize> = Vec::new();
         let mut obj : Option<usize> = None;
-        while try!(input.read_line(&mut buf)) > 0 {
-            // Skip comment
-            if buf.chars().next().unwrap() != '#' {
+        let mut actif_material : Option<usize> = None;
+        let mut actif_smoothing : u32 = 0;
+        let mut forward = Vec::new();
+        while options.max_faces.map_or(true, |m| data.faces.len() < m) {
+            let buf = match lines.next_line(options, nb)? {
+                Some(line) => line,
+                None => break,
+            };
+            // Pass the comments to their handler and skip the blank lines
+            if buf.trim_start().starts_with('#') {
+                comments(buf.trim_start()[1..].trim(), nb);
+            } else if !buf.trim().is_empty() {
                 let mut iter = buf.split_whitespace();
-                let identifier = iter.next();
+                let identifier = iter.next().unwrap();
                 let args : Vec<_> = iter.collect();
-                if identifier.is_none() {continue;}
-                match identifier.unwrap() {
+                let fixed : Vec<String>;
+                let args = if options.decimal_comma && (identifier == "v" || identifier == "vn" || identifier == "vt") {
+                    fixed = args.iter().map(|a| decimal_comma(a)).collect();
+                    fixed.iter().map(String::as_str).collect()
+                } else {
+                    args
+                };
+                match identifier {
                     "v" => {
-                        let values = try!(parse::<f32>(args,nb));
+                        if options.strict && has_trailing_data(&args,3,4) {
+                            return Err(LoadingError::TrailingData(nb));
+                        }
+                        let values = parse::<f32>(args,nb)?;
+                        if options.strict_finite && values.iter().any(|x| !x.is_finite()) {
+                            return Err(LoadingError::NonFinite(nb));
+                        }
                         if values.len() == 4 {
                             data.vertices.push((values[0],values[1],values[2],values[3]));
                         } else if values.len() == 3 {
@@ -143,7 +952,13 @@ impl ObjData {
                         }
                     },
                     "vn" => {
-                        let values = try!(parse::<f32>(args,nb));
+                        if options.strict && has_trailing_data(&args,3,3) {
+                            return Err(LoadingError::TrailingData(nb));
+                        }
+                        let values = parse::<f32>(args,nb)?;
+                        if options.strict_finite && values.iter().any(|x| !x.is_finite()) {
+                            return Err(LoadingError::NonFinite(nb));
+                        }
                         if values.len() == 3 {
                             data.normals.push((values[0],values[1],values[2]));
                         } else {
@@ -151,7 +966,13 @@ impl ObjData {
                         }
                     },
                     "vt" => {
-                        let values = try!(parse::<f32>(args,nb));
+                        if options.strict && has_trailing_data(&args,1,3) {
+                            return Err(LoadingError::TrailingData(nb));
+                        }
+                        let values = parse::<f32>(args,nb)?;
+                        if options.strict_finite && values.iter().any(|x| !x.is_finite()) {
+                            return Err(LoadingError::NonFinite(nb));
+                        }
                         if values.len() == 3 {
                             data.texcoords.push((values[0],values[1],values[2]));
                         } else if values.len() == 2 {
@@ -163,37 +984,69 @@ impl ObjData {
                         }
                     },
                     "s" => {
-                        // Not supported
+                        if args.len() != 1 {
+                            return Err(LoadingError::WrongNumberOfArguments(nb));
+                        }
+                        actif_smoothing = if args[0] == "off" {
+                            0
+                        } else {
+                            parse::<u32>(args,nb)?[0]
+                        };
                     },
                     "f" => {
+                        let joined : String;
+                        let args = if options.face_parse_mode == FaceParseMode::Lenient {
+                            joined = args.join(" ").replace(" /", "/").replace("/ ", "/");
+                            joined.split_whitespace().collect()
+                        } else {
+                            args
+                        };
                         let mut vec : Vec<(usize,Option<usize>,Option<usize>)> = Vec::new();
+                        let mut slashes = Vec::new();
                         if args.len() < 3 {return Err(LoadingError::WrongNumberOfArguments(nb))}
-                        for arg in args {
+                        for (i,arg) in args.into_iter().enumerate() {
                             let index : Vec<_> = arg.split('/').collect();
-                            if index.len() == 0 || index.len() > 3 {
+                            if index.is_empty() || index.len() > 3 {
                                 return Err(LoadingError::WrongNumberOfArguments(nb));
                             }
-                            let v = match index[0].parse::<usize>() {
-                                Ok(val) => val-1,
-                                Err(_) => return Err(LoadingError::Parse(nb)),
+                            if index[0].is_empty() {
+                                return Err(LoadingError::MalformedFace(nb));
+                            }
+                            let v = match resolve_index(index[0], first.vertices, data.vertices.len()) {
+                                Ok(val) => val,
+                                Err(e) => return Err(parse_error(nb, i, arg, e)),
                             };
                             let mut vt = None;
                             if index.len() >= 2 {
-                                vt = match index[1].parse::<usize>().ok() {
-                                    Some(val) => Some(val-1),
-                                    None => None,
-                                };
+                                vt = resolve_index(index[1], first.texcoords, data.texcoords.len()).ok();
                             }
                             let mut vn = None;
                             if index.len() == 3 {
-                                vn = match index[2].parse::<usize>().ok() {
-                                    Some(val) => Some(val-1),
-                                    None => None,
-                                };
+                                vn = resolve_index(index[2], first.normals, data.normals.len()).ok();
+                            }
+                            if options.check_references || options.two_pass {
+                                for (kind,&(index,count)) in [(Some(v),data.vertices.len()),
+                                                              (vt,data.texcoords.len()),
+                                                              (vn,data.normals.len())].iter().enumerate() {
+                                    match index {
+                                        Some(k) if k >= count && options.two_pass => {
+                                            forward.push((nb, i, String::from(arg), kind, k));
+                                        },
+                                        Some(k) if k >= count => {
+                                            return Err(parse_error(nb, i, arg, format!("index beyond the {} {} read so far", count, ELEMENT_KINDS[kind])));
+                                        },
+                                        _ => {},
+                                    }
+                                }
                             }
                             vec.push((v,vt,vn));
+                            slashes.push(index.len() as u8 - 1);
                         }
                         data.faces.push(vec);
+                        data.face_materials.push(actif_material);
+                        data.smoothing_groups.push(actif_smoothing);
+                        data.face_slashes.push(slashes);
+                        data.face_lines.push(nb+1);
                         if obj.is_none() {
                             data.objects.push(Object::new(String::new()));
                             obj = Some(data.objects.len()-1);
@@ -203,43 +1056,504 @@ impl ObjData {
                             data.groups[*g].indexes.insert(data.faces.len()-1);
                         }
                     },
+                    "l" => {
+                        if args.len() < 2 {return Err(LoadingError::WrongNumberOfArguments(nb))}
+                        let mut vec : Vec<(usize,Option<usize>)> = Vec::new();
+                        for (i,arg) in args.into_iter().enumerate() {
+                            let index : Vec<_> = arg.split('/').collect();
+                            if index.len() > 2 {
+                                return Err(LoadingError::WrongNumberOfArguments(nb));
+                            }
+                            let v = match resolve_index(index[0], first.vertices, data.vertices.len()) {
+                                Ok(val) => val,
+                                Err(e) => return Err(parse_error(nb, i, arg, e)),
+                            };
+                            let mut vt = None;
+                            if index.len() == 2 {
+                                vt = resolve_index(index[1], first.texcoords, data.texcoords.len()).ok();
+                            }
+                            vec.push((v,vt));
+                        }
+                        data.lines.push(vec);
+                        let rank = data.line_contexts.len() + data.point_contexts.len();
+                        data.line_contexts.push(PrimitiveContext {faces_before : data.faces.len(), rank,
+                                                                  object : obj, groups : actif_groups.clone()});
+                    },
+                    "p" => {
+                        if args.is_empty() {return Err(LoadingError::WrongNumberOfArguments(nb))}
+                        let mut vec : Vec<usize> = Vec::new();
+                        for (i,arg) in args.into_iter().enumerate() {
+                            match resolve_index(arg, first.vertices, data.vertices.len()) {
+                                Ok(val) => vec.push(val),
+                                Err(e) => return Err(parse_error(nb, i, arg, e)),
+                            }
+                        }
+                        data.points.push(vec);
+                        let rank = data.line_contexts.len() + data.point_contexts.len();
+                        data.point_contexts.push(PrimitiveContext {faces_before : data.faces.len(), rank,
+                                                                   object : obj, groups : actif_groups.clone()});
+                    },
                     "o" => {
-                        if args.len() == 0 {
+                        if args.is_empty() {
                             return Err(LoadingError::WrongNumberOfArguments(nb));
                         }
-                        let mut name = String::new();
-                        let mut args_it = args.iter();
-                        name += args_it.next().unwrap();
-                        for arg in args_it {
-                            name += " ";
-                            name += arg;
-                        }
-                        data.objects.push(Object::new(String::from(name)));
+                        let name = unquote(args.join(" "));
+                        data.objects.push(Object::new(name));
                         obj = Some(data.objects.len()-1);
                     },
+                    "mtllib" => {
+                        if args.is_empty() {
+                            return Err(LoadingError::WrongNumberOfArguments(nb));
+                        }
+                        for arg in args {
+                            data.material_libs.push(String::from(arg));
+                        }
+                    },
+                    "usemtl" => {
+                        actif_material = if args.is_empty() {
+                            None
+                        } else {
+                            let name = unquote(args.join(" "));
+                            match data.materials.iter().position(|m| *m == name) {
+                                Some(i) => Some(i),
+                                None => {
+                                    data.materials.push(name);
+                                    Some(data.materials.len()-1)
+                                },
+                            }
+                        };
+                    },
                     "g" => {
+                        // A bare `g` selects the default group, made of the faces in no group
                         actif_groups.clear();
-                        for arg in args {
+                        for arg in group_names(args) {
                             let mut found = false;
                             for (i,g) in data.groups.iter().enumerate() {
-                                if g.name == arg {
+                                if options.merge_groups_by_name && g.name == arg {
                                     actif_groups.push(i);
                                     found = true;
                                 }
                             }
                             if !found {
-                                data.groups.push(Group::new(String::from(arg)));
+                                data.groups.push(Group::new(arg));
                                 actif_groups.push(data.groups.len()-1);
                             }
                         }
                     },
-                    _ => return Err(LoadingError::InvalidLine(nb)),
+                    id if options.lenient && FREE_FORM_STATEMENTS.contains(&id) => {
+                        // Free-form geometry is not supported, skip it
+                    },
+                    id => {
+                        if !handler(id, &args, nb)? {
+                            return Err(LoadingError::InvalidLine(nb));
+                        }
+                    },
                 }
             }
             nb += 1;
+        }
+        // Resolve the forward references now that all the elements are read
+        let counts = [data.vertices.len(), data.texcoords.len(), data.normals.len()];
+        for &(nb, i, ref arg, kind, k) in forward.iter() {
+            if k >= counts[kind] {
+                return Err(parse_error(nb, i, arg, format!("index beyond the {} {} read", counts[kind], ELEMENT_KINDS[kind])));
+            }
+        }
+        if options.remove_empty_objects {
+            data.remove_empty_objects();
+        }
+        Ok(())
+    }
+
+    /// Count the statements of each kind without building an `ObjData`.
+    ///
+    /// Only the identifier of each line is looked at, so the arguments are not validated.
+    /// Comments and unknown statements are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let counts = ObjData::count_elements(&mut input).ok().unwrap();
+    /// assert_eq!(counts.vertices, 8);
+    /// assert_eq!(counts.faces, 12);
+    /// ```
+    pub fn count_elements<R : io::BufRead>(input : &mut R) -> Result<ElementCounts,LoadingError> {
+        let mut counts = ElementCounts::default();
+        let mut buf = String::new();
+        while input.read_line(&mut buf)? > 0 {
+            match buf.split_whitespace().next() {
+                Some("v") => counts.vertices += 1,
+                Some("vn") => counts.normals += 1,
+                Some("vt") => counts.texcoords += 1,
+                Some("f") => counts.faces += 1,
+                Some("l") => counts.lines += 1,
+                Some("p") => counts.points += 1,
+                Some("o") => counts.objects += 1,
+                Some("g") => counts.groups += 1,
+                _ => {},
+            }
             buf.clear();
         }
-        return Ok(data);
+        Ok(counts)
+    }
+
+    /// Load an `ObjData` from a `BufReader`, comparing the counts announced by comments such
+    /// as `# 8 vertices` or `# Faces: 12` with the vertices and faces loaded, to detect
+    /// truncated files.
+    ///
+    /// The comments are collected during the load through `load_with_comment_handler`. The
+    /// counts of all the comments of a kind are summed, since some exporters write them once
+    /// per object. A warning is returned for each kind whose announced count does not match,
+    /// and kinds without comments are not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\n# 3 vertices\nf 1 2 1\n# 1 faces".as_bytes());
+    /// let (data,warnings) = ObjData::load_checking_counts(&mut input).ok().unwrap();
+    /// assert_eq!(data.vertices.len(), 2);
+    /// assert_eq!(warnings, vec!["3 vertices announced but 2 found"]);
+    /// ```
+    pub fn load_checking_counts<R : io::Read>(input : &mut io::BufReader<R>) -> Result<(ObjData,Vec<String>),LoadingError> {
+        let mut announced_vertices : Option<usize> = None;
+        let mut announced_faces : Option<usize> = None;
+        let data = ObjData::load_with_comment_handler(input, |text,_| {
+            for (kind,n) in count_comment(text) {
+                match kind {
+                    "vertices" => *announced_vertices.get_or_insert(0) += n,
+                    _ => *announced_faces.get_or_insert(0) += n,
+                }
+            }
+        })?;
+        let mut warnings = Vec::new();
+        for &(announced,found,kind) in [(announced_vertices,data.vertices.len(),"vertices"),
+                                        (announced_faces,data.faces.len(),"faces")].iter() {
+            if let Some(n) = announced {
+                if n != found {
+                    warnings.push(format!("{} {} announced but {} found",n,kind,found));
+                }
+            }
+        }
+        Ok((data,warnings))
+    }
+
+    /// Iterate over the faces mutably, to edit their corners in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// for f in data.faces_mut() {
+    ///     f.reverse();
+    /// }
+    /// assert_eq!(data.faces[0][0], (2,None,None));
+    /// ```
+    pub fn faces_mut(&mut self) -> impl Iterator<Item=&mut Face> {
+        self.faces.iter_mut()
+    }
+
+    /// Number of faces.
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// Corners of the face `index`, `None` if there is no such face.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.face(0), Some(&[(0,None,None), (1,None,None), (2,None,None)][..]));
+    /// assert_eq!(data.face(1), None);
+    /// ```
+    pub fn face(&self, index : usize) -> Option<&[Corner]> {
+        self.faces.get(index).map(|f| &f[..])
+    }
+
+    /// Corners of the face `index` to edit in place, `None` if there is no such face.
+    pub fn face_mut(&mut self, index : usize) -> Option<&mut [Corner]> {
+        self.faces.get_mut(index).map(|f| &mut f[..])
+    }
+
+    /// Iterate over the objects with their faces, in the order of `primitives`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// for (o,faces) in data.iter_objects() {
+    ///     assert_eq!(o.name, "Cube");
+    ///     assert_eq!(faces.len(), 12);
+    /// }
+    /// ```
+    pub fn iter_objects<'a>(&'a self) -> impl Iterator<Item=(&'a Object,Vec<&'a Face>)> + 'a {
+        self.objects.iter().map(move |o| (o, o.primitives.iter().map(|&i| &self.faces[i]).collect()))
+    }
+
+    /// Iterate over the groups with their faces, sorted by index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let counts : Vec<_> = data.iter_groups().map(|(g,faces)| (g.name.clone(), faces.len())).collect();
+    /// assert_eq!(counts[0], (String::from("group1"), 6));
+    /// ```
+    pub fn iter_groups<'a>(&'a self) -> impl Iterator<Item=(&'a Group,Vec<&'a Face>)> + 'a {
+        self.groups.iter().map(move |g| {
+            let mut indexes : Vec<usize> = g.indexes.iter().cloned().collect();
+            indexes.sort();
+            (g, indexes.into_iter().map(|i| &self.faces[i]).collect())
+        })
+    }
+
+    /// First object named `name`, the unnamed object being named `""`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.object("Cube").unwrap().primitives.len(), 12);
+    /// assert!(data.object("Sphere").is_none());
+    /// ```
+    pub fn object(&self, name : &str) -> Option<&Object> {
+        self.objects.iter().find(|o| o.name == name)
+    }
+
+    /// Mutable reference to the first object named `name`.
+    pub fn object_mut(&mut self, name : &str) -> Option<&mut Object> {
+        self.objects.iter_mut().find(|o| o.name == name)
+    }
+
+    /// Number of triangles of the faces of the first object named `name`, triangulated as fans.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.object_triangle_count("Cube"), Some(12));
+    /// assert_eq!(data.object_triangle_count("Sphere"), None);
+    /// ```
+    pub fn object_triangle_count(&self, name : &str) -> Option<usize> {
+        self.object(name).map(|o| o.primitives.iter().map(|&i| self.faces[i].len().saturating_sub(2)).sum())
+    }
+
+    /// Group named `name`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\ng gr1\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.group("gr1").unwrap().indexes.len(), 1);
+    /// assert!(data.group("gr2").is_none());
+    /// ```
+    pub fn group(&self, name : &str) -> Option<&Group> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Indices of the faces of the default group, in increasing order.
+    ///
+    /// The default group holds the faces in no group, such as those after a bare `g`, which
+    /// `write` reproduces. A face is moved to it by removing it from every group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\ng gr1\nf 1 2 3\ng\nf 1 3 2".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.default_group(), vec![1]);
+    /// ```
+    pub fn default_group(&self) -> Vec<usize> {
+        (0..self.faces.len()).filter(|i| !self.groups.iter().any(|g| g.indexes.contains(i))).collect()
+    }
+
+    /// Mutable reference to the group named `name`.
+    pub fn group_mut(&mut self, name : &str) -> Option<&mut Group> {
+        self.groups.iter_mut().find(|g| g.name == name)
+    }
+
+    /// Index in `materials` of the material of the face `i`, if it has one.
+    ///
+    /// Faces missing from `face_materials` have no material.
+    pub fn face_material(&self, i : usize) -> Option<usize> {
+        self.face_materials.get(i).cloned().unwrap_or(None)
+    }
+
+    /// Indices of the faces of each material, in increasing order, keyed by the index of the
+    /// material in `materials` and `None` for the faces without material.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 1 2 3\nf 3 2 1";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let faces = data.faces_by_material();
+    /// assert_eq!(faces[&None], vec![0]);
+    /// assert_eq!(faces[&Some(0)], vec![1,2]);
+    /// ```
+    pub fn faces_by_material(&self) -> HashMap<Option<usize>,Vec<usize>> {
+        let mut map : HashMap<Option<usize>,Vec<usize>> = HashMap::new();
+        for i in 0..self.faces.len() {
+            map.entry(self.face_material(i)).or_default().push(i);
+        }
+        map
+    }
+
+    /// Smoothing group of the face `i`, `0` meaning no smoothing.
+    ///
+    /// Faces missing from `smoothing_groups` are not smoothed.
+    pub fn face_smoothing_group(&self, i : usize) -> u32 {
+        self.smoothing_groups.get(i).cloned().unwrap_or(0)
+    }
+
+    /// One-based line of the `f` statement of the face `face_index` in the loaded file.
+    ///
+    /// Faces missing from `face_lines` have none.
+    pub fn face_source_line(&self, face_index : usize) -> Option<usize> {
+        self.face_lines.get(face_index).cloned()
+    }
+
+    /// Number of slashes of the corner `k` of the face `i` as loaded.
+    ///
+    /// Faces missing from `face_slashes`, or whose corners no longer match it, have none.
+    pub fn corner_slashes(&self, i : usize, k : usize) -> Option<u8> {
+        self.face_slashes.get(i).filter(|s| s.len() == self.faces[i].len()).map(|s| s[k])
+    }
+
+    /// Indices of the faces having at least one corner without normal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("f 1//1 2//1 3//1\nf 1//1 2 3//1".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.faces_missing_normals(), vec![1]);
+    /// ```
+    pub fn faces_missing_normals(&self) -> Vec<usize> {
+        self.faces.iter().enumerate()
+            .filter(|&(_,f)| f.iter().any(|&(_,_,vn)| vn.is_none()))
+            .map(|(i,_)| i)
+            .collect()
+    }
+
+    /// Test if at least one face corner has a normal.
+    pub fn has_normals(&self) -> bool {
+        self.faces.iter().flatten().any(|c| c.2.is_some())
+    }
+
+    /// Test if at least one face corner has texture coordinates.
+    pub fn has_texcoords(&self) -> bool {
+        self.faces.iter().flatten().any(|c| c.1.is_some())
+    }
+
+    /// Test if every corner of every face has a normal, which holds when there is no face.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("f 1//1 2//1 3//1\nf 1//1 2 3//1".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert!(data.has_normals());
+    /// assert!(!data.all_faces_have_normals());
+    /// ```
+    pub fn all_faces_have_normals(&self) -> bool {
+        self.faces.iter().flatten().all(|c| c.2.is_some())
+    }
+
+    /// Test if every corner of every face has texture coordinates, which holds when there is no face.
+    pub fn all_faces_have_texcoords(&self) -> bool {
+        self.faces.iter().flatten().all(|c| c.1.is_some())
+    }
+
+    /// Ranges `min..max+1` of the indices of the vertices, texture coordinates and normals
+    /// referenced by the faces, empty when none is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("f 3//2 5//2 4//2\nf 4 5 6".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.index_ranges(), (2..6, 0..0, 1..2));
+    /// ```
+    pub fn index_ranges(&self) -> (Range<usize>,Range<usize>,Range<usize>) {
+        fn extend(range : &mut Option<Range<usize>>, i : usize) {
+            *range = Some(match range.take() {
+                Some(r) => r.start.min(i)..r.end.max(i+1),
+                None => i..i+1,
+            });
+        }
+        let mut ranges = (None,None,None);
+        for &(v,vt,vn) in self.faces.iter().flatten() {
+            extend(&mut ranges.0, v);
+            if let Some(t) = vt {
+                extend(&mut ranges.1, t);
+            }
+            if let Some(n) = vn {
+                extend(&mut ranges.2, n);
+            }
+        }
+        (ranges.0.unwrap_or(0..0), ranges.1.unwrap_or(0..0), ranges.2.unwrap_or(0..0))
     }
 
     /// Write in wavefront format in file.
@@ -260,72 +1574,309 @@ impl ObjData {
     /// assert!(data.write(&mut output).is_ok());
     /// ```
     pub fn write<W : io::Write>(&self, output : &mut io::BufWriter<W>) -> Result<(),LoadingError> {
+        self.write_with_options(output, &WriteOptions::default())
+    }
+
+    /// Write in wavefront format in file with the given `WriteOptions`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufWriter;
+    /// use lwobj::{ObjData,WriteOptions};
+    ///
+    /// let mut data = ObjData::new();
+    /// data.vertices.push((1.,2.,3.,1.));
+    /// let options = WriteOptions { header : true, comment : Some(String::from("A point")), ..Default::default() };
+    /// let mut output = BufWriter::new(Vec::<u8>::new());
+    /// assert!(data.write_with_options(&mut output, &options).is_ok());
+    /// let buf = output.into_inner().unwrap();
+    /// assert_eq!(String::from_utf8(buf).unwrap(), "# Generated by lwobj\n# A point\nv 1 2 3 1\n");
+    /// ```
+    pub fn write_with_options<W : io::Write>(&self, output : &mut io::BufWriter<W>, options : &WriteOptions) -> Result<(),LoadingError> {
+        self.write_impl(output, options, IndexOffsets::default())
+    }
+
+    /// Write in wavefront format after the elements counted by `offsets`, already written
+    /// in `output`.
+    ///
+    /// The face indices are shifted by the given offsets, so several meshes can be streamed
+    /// one after the other in the same file without merging them in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{IndexOffsets, ObjData};
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let mut output = Vec::new();
+    /// assert!(data.write_append(&mut output, IndexOffsets::default()).is_ok());
+    /// let offsets = IndexOffsets { vertices : 3, normals : 0, texcoords : 0 };
+    /// assert!(data.write_append(&mut output, offsets).is_ok());
+    /// let mut input = BufReader::new(&output[..]);
+    /// let both = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(both.faces[1], vec![(3,None,None), (4,None,None), (5,None,None)]);
+    /// ```
+    pub fn write_append<W : io::Write>(&self, output : &mut W, offsets : IndexOffsets) -> Result<(),LoadingError> {
+        self.write_impl(output, &WriteOptions::default(), offsets)
+    }
+
+    /// Write each object in its own file, with the writer returned by `make_writer` for its name.
+    ///
+    /// Each file holds a self-contained mesh, with only the elements used by the object and
+    /// its indices rebased accordingly, as given by `split_objects`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\no a\nf 1 2 3\no b\nf 2 4 3";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let mut names = Vec::new();
+    /// assert!(data.write_split_by_object(|name| { names.push(name.to_string()); Ok(Vec::new()) }).is_ok());
+    /// assert_eq!(names, vec!["a", "b"]);
+    /// ```
+    pub fn write_split_by_object<W,F>(&self, mut make_writer : F) -> Result<(),LoadingError>
+        where W : io::Write, F : FnMut(&str) -> io::Result<W> {
+        for (name,part) in self.split_objects() {
+            let mut output = io::BufWriter::new(make_writer(&name)?);
+            part.write(&mut output)?;
+            io::Write::flush(&mut output)?;
+        }
+        Ok(())
+    }
+
+    fn write_impl<W : io::Write>(&self, output : &mut W, options : &WriteOptions,
+                                 offsets : IndexOffsets) -> Result<(),LoadingError> {
+        // Write header
+        if options.header {
+            output.write_all("# Generated by lwobj\n".as_bytes())?;
+        }
+        if let Some(ref comment) = options.comment {
+            for line in comment.lines() {
+                output.write_all(format!("# {}\n",line).as_bytes())?;
+            }
+        }
+
+        // Write material libraries
+        for lib in &self.material_libs {
+            let line : String = format!("mtllib {}\n",lib);
+            output.write_all(line.as_bytes())?;
+        }
+
         // Write vertices
+        let f = |x : f32| options.format(x);
         for &(x,y,z,w) in &self.vertices {
-            let line : String = format!("v {} {} {} {}\n",x,y,z,w);
-            try!(output.write_all(line.as_bytes()));
+            let line : String = if options.omit_unit_weight && w == 1. {
+                format!("v {} {} {}\n",f(x),f(y),f(z))
+            } else {
+                format!("v {} {} {} {}\n",f(x),f(y),f(z),f(w))
+            };
+            output.write_all(line.as_bytes())?;
         }
 
         // Write normals
         for &(x,y,z) in &self.normals {
-            let line : String = format!("vn {} {} {}\n",x,y,z);
-            try!(output.write_all(line.as_bytes()));
+            let line : String = format!("vn {} {} {}\n",f(x),f(y),f(z));
+            output.write_all(line.as_bytes())?;
         }
 
         // Write texcoords
         for &(u,v,w) in &self.texcoords {
-            let line : String = format!("vt {} {} {}\n",u,v,w);
-            try!(output.write_all(line.as_bytes()));
+            let line : String = format!("vt {} {} {}\n",f(u),f(v),f(w));
+            output.write_all(line.as_bytes())?;
         }
+        let default_texcoord = if options.explicit_default_texcoord
+            && self.faces.iter().any(|f| f.iter().any(|c| c.1.is_none() && c.2.is_some())) {
+            output.write_all(format!("vt {} {}\n",f(0.),f(0.)).as_bytes())?;
+            Some(self.texcoords.len())
+        } else {
+            None
+        };
+
+        // Lines and points declared in an object are written among its faces, in their groups
+        let mut elements : Vec<(&PrimitiveContext,bool,usize)> = Vec::new();
+        elements.extend(self.line_contexts.iter().take(self.lines.len()).enumerate().map(|(i,c)| (c,false,i)));
+        elements.extend(self.point_contexts.iter().take(self.points.len()).enumerate().map(|(i,c)| (c,true,i)));
+        elements.retain(|e| e.0.object.map_or(true, |o| o < self.objects.len()) && e.0.groups.iter().all(|&g| g < self.groups.len()));
+        elements.sort_by_key(|e| e.0.rank);
+        let mut written = (vec![false; self.lines.len()],vec![false; self.points.len()]);
 
         // Write faces
         let mut actif_groups : Vec<usize> = Vec::new();
-        for o in &self.objects {
+        let mut actif_material : Option<usize> = None;
+        let mut actif_smoothing : u32 = 0;
+        for &(c,point,i) in elements.iter().filter(|e| e.0.object.is_none()) {
+            self.write_element(output, c, point, i, &mut actif_groups, offsets)?;
+            if point {written.1[i] = true} else {written.0[i] = true}
+        }
+        for (oi,o) in self.objects.iter().enumerate() {
             if o.name != String::new() {
-                let line : String = format!("o {}\n",o.name);
-                try!(output.write_all(line.as_bytes()));
+                let line : String = format!("o {}\n",quote(&o.name));
+                output.write_all(line.as_bytes())?;
             }
-            for i in &o.primitives {
-                let mut groups : Vec<usize> = Vec::new();
-                for (j,g) in self.groups.iter().enumerate() {
-                    if g.indexes.contains(i) {
-                        groups.push(j);
+            let mut pending = elements.iter().filter(|e| e.0.object == Some(oi)).peekable();
+            let mut primitives : Vec<(&usize,Vec<usize>)> = o.primitives.iter().map(|i| {
+                let groups : Vec<usize> = (0..self.groups.len()).filter(|&j| self.groups[j].indexes.contains(i)).collect();
+                (i,groups)
+            }).collect();
+            if options.sort_by_group {
+                // Stable sort by first appearance of the groups
+                let mut order : Vec<Vec<usize>> = Vec::new();
+                for (_,groups) in &primitives {
+                    if !order.contains(groups) {
+                        order.push(groups.clone());
                     }
                 }
+                primitives.sort_by_key(|(_,groups)| order.iter().position(|g| g == groups));
+            }
+            for (i,groups) in primitives {
+                while let Some(&&(c,point,k)) = pending.peek() {
+                    if c.faces_before > *i {
+                        break;
+                    }
+                    self.write_element(output, c, point, k, &mut actif_groups, offsets)?;
+                    if point {written.1[k] = true} else {written.0[k] = true}
+                    pending.next();
+                }
                 if actif_groups != groups {
                     actif_groups = groups;
-                    try!(output.write_all("g".as_bytes()));
-                    for g in &actif_groups {
-                        try!(output.write_all(" ".as_bytes()));
-                        try!(output.write_all(&self.groups[*g].name.as_bytes()));
-                    }
-                    try!(output.write_all("\n".as_bytes()));
+                    self.write_groups(output, &actif_groups)?;
+                }
+
+                let material = self.face_material(*i);
+                if actif_material != material {
+                    actif_material = material;
+                    let line : String = match material {
+                        Some(m) => format!("usemtl {}\n",quote(&self.materials[m])),
+                        None => String::from("usemtl\n"),
+                    };
+                    output.write_all(line.as_bytes())?;
+                }
+
+                let smoothing = self.face_smoothing_group(*i);
+                if actif_smoothing != smoothing {
+                    actif_smoothing = smoothing;
+                    let line : String = match smoothing {
+                        0 => String::from("s off\n"),
+                        s => format!("s {}\n",s),
+                    };
+                    output.write_all(line.as_bytes())?;
                 }
 
-                try!(output.write_all("f".as_bytes()));
-                for &(v,vt,vn) in &self.faces[*i] {
+                output.write_all("f".as_bytes())?;
+                // Faces made of vertex indices only are written without slashes
+                let bare = self.faces[*i].iter().all(|c| c.1.is_none() && c.2.is_none());
+                for (k,&(v,vt,vn)) in self.faces[*i].iter().enumerate() {
+                    let vt = if vn.is_some() {vt.or(default_texcoord)} else {vt};
+                    let needed = if vn.is_some() {2} else if vt.is_some() {1} else {0};
+                    let recorded = if options.preserve_slashes {self.corner_slashes(*i,k)} else {None};
+                    let slashes = match recorded {
+                        Some(s) => s.max(needed),
+                        None if bare => 0,
+                        None if !options.trailing_slash => needed,
+                        None => 2,
+                    };
                     let vt_str = match vt {
-                        Some(val) => (val+1).to_string(),
+                        Some(val) => (offsets.texcoords+val+1).to_string(),
                         None => "".to_string(),
                     };
                     let vn_str = match vn {
-                        Some(val) => (val+1).to_string(),
+                        Some(val) => (offsets.normals+val+1).to_string(),
                         None => "".to_string(),
                     };
-                    let arg : String = format!(" {}/{}/{}",v+1,vt_str,vn_str);
-                    try!(output.write_all(arg.as_bytes()));
+                    let arg : String = match slashes {
+                        0 => format!(" {}",offsets.vertices+v+1),
+                        1 => format!(" {}/{}",offsets.vertices+v+1,vt_str),
+                        _ => format!(" {}/{}/{}",offsets.vertices+v+1,vt_str,vn_str),
+                    };
+                    output.write_all(arg.as_bytes())?;
                 }
-                try!(output.write_all("\n".as_bytes()));
+                output.write_all("\n".as_bytes())?;
+            }
+            for &(c,point,k) in pending {
+                self.write_element(output, c, point, k, &mut actif_groups, offsets)?;
+                if point {written.1[k] = true} else {written.0[k] = true}
+            }
+        }
+
+        // Write the remaining lines
+        for i in (0..self.lines.len()).filter(|&i| !written.0[i]) {
+            self.write_line(output, i, offsets)?;
+        }
+
+        // Write the remaining points
+        for i in (0..self.points.len()).filter(|&i| !written.1[i]) {
+            self.write_point(output, i, offsets)?;
+        }
+        Ok(())
+    }
+
+    /// Write a `g` statement selecting `groups`.
+    fn write_groups<W : io::Write>(&self, output : &mut W, groups : &[usize]) -> Result<(),LoadingError> {
+        output.write_all("g".as_bytes())?;
+        for g in groups {
+            if !self.groups[*g].name.is_empty() {
+                output.write_all(" ".as_bytes())?;
+                output.write_all(quote(&self.groups[*g].name).as_bytes())?;
             }
         }
+        output.write_all("\n".as_bytes())?;
+        Ok(())
+    }
+
+    /// Write the line or point `i` in its groups, switching `actif_groups` to them.
+    fn write_element<W : io::Write>(&self, output : &mut W, context : &PrimitiveContext, point : bool, i : usize,
+                                    actif_groups : &mut Vec<usize>, offsets : IndexOffsets) -> Result<(),LoadingError> {
+        if *actif_groups != context.groups {
+            *actif_groups = context.groups.clone();
+            self.write_groups(output, actif_groups)?;
+        }
+        if point {
+            self.write_point(output, i, offsets)
+        } else {
+            self.write_line(output, i, offsets)
+        }
+    }
+
+    fn write_line<W : io::Write>(&self, output : &mut W, i : usize, offsets : IndexOffsets) -> Result<(),LoadingError> {
+        output.write_all("l".as_bytes())?;
+        for &(v,vt) in &self.lines[i] {
+            let arg : String = match vt {
+                Some(val) => format!(" {}/{}",offsets.vertices+v+1,offsets.texcoords+val+1),
+                None => format!(" {}",offsets.vertices+v+1),
+            };
+            output.write_all(arg.as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+        Ok(())
+    }
+
+    fn write_point<W : io::Write>(&self, output : &mut W, i : usize, offsets : IndexOffsets) -> Result<(),LoadingError> {
+        output.write_all("p".as_bytes())?;
+        for &v in &self.points[i] {
+            output.write_all(format!(" {}",offsets.vertices+v+1).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::io;
     use std::io::BufReader;
     use std::io::BufWriter;
+    use std::io::Write;
     use std::str;
     use obj::*;
 
@@ -341,7 +1892,197 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::InvalidLine(line) => assert!(line == 1),
-            _ => assert!(false),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_blank_lines() {
+        let mut input = BufReader::new("v 1 2 3\n\nv 4 5 6".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![(1.,2.,3.,1.),(4.,5.,6.,1.)],data.vertices);
+
+        // Blank lines still count when reporting errors
+        let mut input = BufReader::new("v 1 2 3\n\n  \t\nv 4 5".as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(line) => assert!(line == 3),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn load_file_endings() {
+        for obj_str in ["v 1 2 3\nv 4 5 6", "v 1 2 3\nv 4 5 6\n\n", "v 1 2 3\nv 4 5 6\n \t ",
+                        "v 1 2 3\r\nv 4 5 6\r\n\r\n", "v 1 2 3\r\nv 4 5 6\r\n\r", "v 1 2 3\nv 4 5 6\n\t\r\n"].iter() {
+            let mut input = BufReader::new(obj_str.as_bytes());
+            let data = ObjData::load(&mut input).ok().unwrap();
+            assert_eq!(vec![(1.,2.,3.,1.),(4.,5.,6.,1.)],data.vertices);
+        }
+    }
+
+    #[test]
+    fn load_indented_comments() {
+        let mut input = BufReader::new("    # comment\nv 1 1 1".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![(1.,1.,1.,1.)],data.vertices);
+
+        let mut input = BufReader::new("\t# blender export\nv 1 1 1\n  \t#v 2 2 2".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(1,data.vertices.len());
+    }
+
+    #[test]
+    fn load_with_handler() {
+        let obj_str =
+        r#"o Test
+        myext 1 2
+        v 1 -1 3.
+        myext a
+        other 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut handled = Vec::new();
+        let res = ObjData::load_with_handler(&mut input, |id, args, line| {
+            if id == "myext" {
+                handled.push((args.join(" "), line));
+                return Ok(true);
+            }
+            Ok(false)
+        });
+        match res.err().unwrap() {
+            LoadingError::InvalidLine(line) => assert!(line == 4),
+            _ => panic!(),
+        };
+        assert_eq!(vec![(String::from("1 2"),1), (String::from("a"),3)], handled);
+
+        let obj_str =
+        r#"myext
+        v 1 -1 3."#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let res = ObjData::load_with_handler(&mut input, |_, _, line| Err(LoadingError::WrongNumberOfArguments(line)));
+        match res.err().unwrap() {
+            LoadingError::WrongNumberOfArguments(line) => assert!(line == 0),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_free_form_lenient() {
+        let obj_str =
+        r#"v 1 -1 3.
+        v -1 -1 1 0.5
+        vp 0.5 0.5
+        cstype rat bspline
+        deg 2
+        curv 0.0 1.0 1 2
+        parm u 0.0 0.0 1.0 1.0
+        trim 0.0 1.0 1
+        scrv 0.0 1.0 1
+        sp 1 2
+        end
+        v 0 0 0
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::InvalidLine(line) => assert!(line == 2),
+            _ => panic!(),
+        };
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let options = LoadOptions { lenient : true, ..Default::default() };
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(vec![(1.,-1.,3.,1.), (-1.,-1.,1.,0.5), (0.,0.,0.,1.)],data.vertices);
+        assert_eq!(1,data.faces.len());
+    }
+
+    #[test]
+    fn load_lossy() {
+        let obj_str = b"o Caf\xe9 cr\xe8me\nv 1 -1 3.\nf 1 1 1";
+
+        let mut input = BufReader::new(&obj_str[..]);
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Io(_) => {},
+            _ => panic!(),
+        };
+
+        let mut input = BufReader::new(&obj_str[..]);
+        let data = ObjData::load_lossy(&mut input).ok().unwrap();
+        assert_eq!("Caf\u{FFFD} cr\u{FFFD}me",data.objects[0].name);
+        assert_eq!(vec![(1.,-1.,3.,1.)],data.vertices);
+        assert_eq!(1,data.faces.len());
+    }
+
+    #[test]
+    fn load_strict() {
+        let options = LoadOptions { strict : true, ..Default::default() };
+        for &(obj_str,line) in [("v 1 2 3\nv 1 2 3 extra", 1), ("v 1 2 3 4 5", 0),
+                                ("vn 1 2 3 4", 0), ("v 1 2 3\nvt 1 2 3 4", 1)].iter() {
+            let mut input = BufReader::new(obj_str.as_bytes());
+            match ObjData::load(&mut input).err().unwrap() {
+                LoadingError::Parse(_,_) | LoadingError::WrongNumberOfArguments(_) => {},
+                _ => panic!(),
+            };
+            let mut input = BufReader::new(obj_str.as_bytes());
+            match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+                LoadingError::TrailingData(l) => assert!(l == line),
+                _ => panic!(),
+            };
+        }
+
+        let mut input = BufReader::new("v 1 x 3".as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::Parse(line,_) => assert!(line == 0),
+            _ => panic!(),
+        };
+        let mut input = BufReader::new("v 1 2".as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(line) => assert!(line == 0),
+            _ => panic!(),
+        };
+        let mut input = BufReader::new("v 1 2 3 0.5\nvt 0.5\nvn 0 0 1".as_bytes());
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(vec![(1.,2.,3.,0.5)],data.vertices);
+    }
+
+    #[test]
+    fn load_from_path() {
+        let dir = env::temp_dir().join("lwobj_load_from_path");
+        fs::create_dir_all(dir.join("materials")).unwrap();
+        let obj_path = dir.join("model.obj");
+        fs::write(&obj_path, "mtllib materials/model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3").unwrap();
+        fs::write(dir.join("materials").join("model.mtl"), "newmtl red\nKd 1 0 0").unwrap();
+
+        let data = ObjData::load_from_path(&obj_path).ok().unwrap();
+        assert_eq!(vec!["materials/model.mtl"],data.material_libs);
+        assert_eq!(Some(dir.clone()),data.base_dir);
+        let paths = data.material_lib_paths();
+        assert_eq!(vec![dir.join("materials/model.mtl")],paths);
+        assert!(paths[0].is_file());
+
+        let mut input = BufReader::new(fs::File::open(&obj_path).unwrap());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(None,data.base_dir);
+        assert_eq!(vec![PathBuf::from("materials/model.mtl")],data.material_lib_paths());
+
+        match ObjData::load_from_path(dir.join("missing.obj")).err().unwrap() {
+            LoadingError::Io(_) => {},
+            _ => panic!(),
+        };
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn try_from_bytes() {
+        let bytes = b"v 1 -1 3.\nv -1 -1 1 0.5\nv 0 0 0\no Test\nf 1 2 3";
+        let data = ObjData::try_from(&bytes[..]).ok().unwrap();
+        assert_eq!(vec![(1.,-1.,3.,1.), (-1.,-1.,1.,0.5), (0.,0.,0.,1.)],data.vertices);
+        assert_eq!("Test",data.objects[0].name);
+
+        match ObjData::try_from(&b"v 1 2\nf 1 2 3"[..]).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(line) => assert!(line == 0),
+            _ => panic!(),
         };
     }
 
@@ -375,7 +2116,7 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            _ => panic!(),
         };
     }
 
@@ -390,8 +2131,27 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::Parse(line) => assert!(line == 3),
-            _ => assert!(false),
+            LoadingError::Parse(line,details) => {
+                assert!(line == 3);
+                assert_eq!(1,details.token);
+                assert_eq!("-1d",details.text);
+                assert_eq!("invalid float literal",details.message);
+            },
+            _ => panic!(),
+        };
+
+        let mut input = BufReader::new("v 0 0 0\nf 1 2 x".as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(line,details) => {
+                assert!(line == 1);
+                assert_eq!((2,"x"),(details.token,details.text.as_str()));
+            },
+            _ => panic!(),
+        };
+        let mut input = BufReader::new("v 0 0 0\np 1 -2".as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(_,details) => assert!(details.message.contains("1 elements")),
+            _ => panic!(),
         };
     }
 
@@ -425,7 +2185,7 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            _ => panic!(),
         };
 
         let obj_str =
@@ -438,7 +2198,7 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            _ => panic!(),
         };
     }
 
@@ -481,6 +2241,102 @@ mod tests {
         assert_eq!(expected,data.faces);
     }
 
+    #[test]
+    fn load_faces_negative_indices() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        f -1 -2 -3
+        vt 0 0
+        vt 1 1
+        vn 0 0 1
+        v 0 1 0
+        f -3/-1/-1 -2/-2/-1 -1/1
+        f 1 -4 2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![(2,None,None),(1,None,None),(0,None,None)],data.faces[0]);
+        assert_eq!(vec![(1,Some(1),Some(0)),(2,Some(0),Some(0)),(3,Some(0),None)],data.faces[1]);
+        assert_eq!(vec![(0,None,None),(0,None,None),(1,None,None)],data.faces[2]);
+
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        assert!(!written.contains('-'));
+        assert!(written.contains("f 2/2/1 3/1/1 4/1\n"));
+        let mut input = BufReader::new(written.as_bytes());
+        let reloaded = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.faces,reloaded.faces);
+
+        for s in ["f -1 -2 -4","f 0 1 2"].iter() {
+            let obj_str = format!("v 0 0 0\nv 1 0 0\nv 1 1 0\n{}",s);
+            let mut input = BufReader::new(obj_str.as_bytes());
+            match ObjData::load(&mut input).err().unwrap() {
+                LoadingError::Parse(line,_) => assert!(line == 3),
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn load_faces_before_vertices() {
+        let obj_str =
+        r#"f 1/1/1 2/2/1 3/3/1
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 0 1
+        vn 0 0 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![vec![(0,Some(0),Some(0)), (1,Some(1),Some(0)), (2,Some(2),Some(0))]],data.faces);
+        assert_eq!(3,data.vertices.len());
+        assert_eq!(3,data.texcoords.len());
+        assert_eq!(1,data.normals.len());
+    }
+
+    #[test]
+    fn load_two_pass() {
+        let obj_str =
+        r#"f 1/1/1 2/2/1 3/3/1
+        f 1 3 4
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 0 1
+        vn 0 0 1
+        v 1 1 0"#;
+        let options = LoadOptions { two_pass : true, ..Default::default() };
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(vec![vec![(0,Some(0),Some(0)), (1,Some(1),Some(0)), (2,Some(2),Some(0))],
+                        vec![(0,None,None), (2,None,None), (3,None,None)]],data.faces);
+        assert_eq!(4,data.vertices.len());
+
+        let options = LoadOptions { two_pass : true, check_references : true, ..Default::default() };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        assert_eq!(2,ObjData::load_with_options(&mut input, &options).ok().unwrap().faces.len());
+
+        let mut input = BufReader::new("f 1 2 5\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0".as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::Parse(line,details) => {
+                assert_eq!(0,line);
+                assert_eq!(2,details.token);
+                assert_eq!("5",details.text);
+            },
+            _ => panic!(),
+        };
+    }
+
     #[test]
     fn load_faces_wrong_number_of_arguments() {
         let obj_str =
@@ -493,7 +2349,7 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::WrongNumberOfArguments(line) => assert!(line == 3),
-            _ => assert!(false),
+            _ => panic!(),
         };
 
         let obj_str =
@@ -507,7 +2363,7 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            _ => panic!(),
         };
     }
 
@@ -521,11 +2377,472 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::Parse(line) => assert!(line == 2),
-            _ => assert!(false),
+            LoadingError::Parse(line,_) => assert!(line == 2),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_faces_parse_mode() {
+        let obj_str =
+        r#"f 1/1/1 2/2/2 3/3/3
+        f 1 / 1 / 1 2/2/2 3 /3/ 3
+        f 1 // 1 2 //2 3// 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::MalformedFace(line) => assert!(line == 1),
+            _ => panic!(),
+        };
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let options = LoadOptions { face_parse_mode : FaceParseMode::Lenient, ..Default::default() };
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(3,data.faces.len());
+        assert_eq!(data.faces[0],data.faces[1]);
+        assert_eq!(vec![(0,None,Some(0)), (1,None,Some(1)), (2,None,Some(2))],data.faces[2]);
+
+        let mut input = BufReader::new("f 1 2 3 / 4".as_bytes());
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(vec![(0,None,None), (1,None,None), (2,Some(3),None)],data.faces[0]);
+        let mut input = BufReader::new("f /1 2 3".as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::MalformedFace(line) => assert!(line == 0),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_merge_groups_by_name() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        g mygrp
+        f 1 2 3
+        g other
+        f 1 2 3
+        g mygrp
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(2,data.groups.len());
+        assert_eq!([0,2].iter().cloned().collect::<HashSet<usize>>(),data.group("mygrp").unwrap().indexes);
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let options = LoadOptions { merge_groups_by_name : false, ..Default::default() };
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(3,data.groups.len());
+        let names : Vec<&str> = data.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(vec!["mygrp","other","mygrp"],names);
+        assert_eq!([0].iter().cloned().collect::<HashSet<usize>>(),data.groups[0].indexes);
+        assert_eq!([2].iter().cloned().collect::<HashSet<usize>>(),data.groups[2].indexes);
+    }
+
+    #[test]
+    fn load_limited() {
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let limited = ObjData::load_limited(&mut input, 3).ok().unwrap();
+        assert_eq!(data.faces[..3],limited.faces[..]);
+        assert_eq!(data.vertices,limited.vertices);
+        assert_eq!(data.normals,limited.normals);
+        assert!(limited.groups.is_empty());
+
+        let mut rest = String::new();
+        io::Read::read_to_string(&mut input, &mut rest).unwrap();
+        assert!(rest.starts_with("g group1 group2\n"));
+    }
+
+    #[test]
+    fn face_source_line() {
+        let obj_str = "# Two objects\nv 0 0 0\nv 1 0 0\nv 0 1 0\no First\nf 1 2 3\nf 3 2 1\n\no Second\ng gr1\nf 1 3 2\n";
+        for lossy in [false,true].iter() {
+            let mut input = BufReader::new(obj_str.as_bytes());
+            let options = LoadOptions { lossy : *lossy, ..Default::default() };
+            let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+            assert_eq!(vec![6,7,11],data.face_lines);
+            assert_eq!(Some(11),data.face_source_line(2));
+            assert_eq!(None,data.face_source_line(3));
+        }
+
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(21),data.face_source_line(2));
+    }
+
+    #[test]
+    fn load_decimal_comma() {
+        let obj_str = "v 1,5 2,0 3,0\nvn +0,0 -1 0\nvt 0,25 1";
+        let mut input = BufReader::new(obj_str.as_bytes());
+        assert!(ObjData::load(&mut input).is_err());
+
+        let options = LoadOptions { decimal_comma : true, ..Default::default() };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        assert_eq!(vec![(1.5,2.0,3.0,1.0)],data.vertices);
+        assert_eq!(vec![(0.,-1.,0.)],data.normals);
+        assert_eq!(vec![(0.25,1.,0.)],data.texcoords);
+
+        let mut input = BufReader::new("v 1,5,2 0 0".as_bytes());
+        assert!(ObjData::load_with_options(&mut input, &options).is_err());
+    }
+
+    #[test]
+    fn load_remove_empty_objects() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\no Empty\no Mesh\nf 1 2 3\no Wire\nl 1 2\no Extra\n";
+        let mut input = BufReader::new(obj_str.as_bytes());
+        assert_eq!(4,ObjData::load(&mut input).ok().unwrap().objects.len());
+
+        let options = LoadOptions { remove_empty_objects : true, ..Default::default() };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load_with_options(&mut input, &options).ok().unwrap();
+        let names : Vec<&str> = data.objects.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(vec!["Mesh","Wire"],names);
+        assert_eq!(vec![0],data.objects[0].primitives);
+        assert_eq!(Some(1),data.line_contexts[0].object);
+    }
+
+    #[test]
+    fn load_extend() {
+        let mut data = ObjData::new();
+        for _ in 0..2 {
+            let f = File::open("cube.obj").unwrap();
+            let mut input = BufReader::new(f);
+            assert!(data.load_extend(&mut input).is_ok());
+        }
+        let cube = ObjData::load(&mut BufReader::new(File::open("cube.obj").unwrap())).ok().unwrap();
+        assert_eq!(16,data.vertices.len());
+        assert_eq!(12,data.normals.len());
+        assert_eq!(24,data.faces.len());
+        for (f,c) in data.faces[12..].iter().zip(&cube.faces) {
+            let shifted : Vec<_> = c.iter().map(|&(v,vt,vn)| (v+8,vt,vn.map(|n| n+6))).collect();
+            assert_eq!(&shifted,f);
+        }
+        assert_eq!(2,data.objects.len());
+        assert_eq!((12..24).collect::<Vec<_>>(),data.objects[1].primitives);
+        assert_eq!(cube.groups.len(),data.groups.len());
+        assert_eq!(Some(cube.face_lines[0]),data.face_source_line(12));
+
+        // Relative indices stay within the loaded input
+        let mut input = BufReader::new("v 0 0 0\nf -1 -1 -2".as_bytes());
+        assert!(data.load_extend(&mut input).is_err());
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1".as_bytes());
+        assert!(data.load_extend(&mut input).is_ok());
+        assert_eq!(&vec![(17,None,None),(18,None,None),(19,None,None)],data.faces.last().unwrap());
+    }
+
+    #[test]
+    fn load_strict_finite() {
+        let mut input = BufReader::new("v 1 nan 3".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.vertices[0].1.is_nan());
+
+        let options = LoadOptions { strict_finite : true, ..Default::default() };
+        for (obj_str,line) in [("v 1 nan 3",0), ("v 0 0 0\nvn 0 inf 0",1), ("vt 0\nvt 1e400",1)].iter() {
+            let mut input = BufReader::new(obj_str.as_bytes());
+            match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+                LoadingError::NonFinite(l) => assert_eq!(*line,l),
+                _ => panic!(),
+            };
+        }
+        let mut input = BufReader::new("v 1 -2.5e3 3 1".as_bytes());
+        assert!(ObjData::load_with_options(&mut input, &options).is_ok());
+    }
+
+    #[test]
+    fn faces_by_material() {
+        let obj_str =
+        r#"mtllib materials.mtl
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        usemtl red
+        f 1 2 3
+        usemtl blue
+        f 1 3 4
+        f 2 3 4
+        usemtl red
+        f 1 2 4"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let faces = data.faces_by_material();
+        assert_eq!(2,faces.len());
+        assert_eq!(vec![0,3],faces[&Some(0)]);
+        assert_eq!(vec![1,2],faces[&Some(1)]);
+        assert!(!faces.contains_key(&None));
+        assert!(ObjData::new().faces_by_material().is_empty());
+    }
+
+    #[test]
+    fn load_max_line_length() {
+        let options = LoadOptions { max_line_length : Some(16), ..Default::default() };
+        let mut input = BufReader::new("v 0 0 0\nv 1.5 0 0.25\r\nv 0 1 0\nf 1 2 3".as_bytes());
+        assert_eq!(3,ObjData::load_with_options(&mut input, &options).ok().unwrap().vertices.len());
+
+        // The long line is not read past the limit
+        let long = format!("v 0 0 0\nf{}\nf 1 1 1\n", " 1".repeat(100000));
+        let mut input = BufReader::with_capacity(64, long.as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::LineTooLong(line) => assert_eq!(1,line),
+            _ => panic!(),
+        };
+        let remaining = io::BufRead::fill_buf(&mut input).unwrap().len();
+        assert!(remaining > 0 && remaining <= 64);
+
+        // Bytes are counted before being replaced
+        let lossy = LoadOptions { lossy : true, ..options };
+        let mut input = BufReader::new(&b"v 0 0 0 \xff\xff\xff\xff\xff\xff\xff\xff\xff\n"[..]);
+        match ObjData::load_with_options(&mut input, &lossy).err().unwrap() {
+            LoadingError::LineTooLong(line) => assert_eq!(0,line),
+            _ => panic!(),
+        };
+        let mut input = BufReader::new(&b"g \xffname\n"[..]);
+        assert_eq!("\u{FFFD}name",ObjData::load_with_options(&mut input, &lossy).ok().unwrap().groups[0].name);
+        let mut input = BufReader::new(&b"g \xffname\n"[..]);
+        assert!(ObjData::load_with_options(&mut input, &options).is_err());
+    }
+
+    #[test]
+    fn load_check_references() {
+        let obj_str =
+        r#"f 5 6 7
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 0
+        v 0 0 1
+        v 1 0 1
+        v 0 1 1"#;
+        let options = LoadOptions { check_references : true, ..Default::default() };
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        assert_eq!(1,ObjData::load(&mut input).ok().unwrap().faces.len());
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::Parse(line,details) => {
+                assert_eq!(0,line);
+                assert_eq!(0,details.token);
+                assert_eq!("5",details.text);
+            },
+            _ => panic!(),
+        };
+
+        let mut input = BufReader::new("v 0 0 0\nvn 0 0 1\nf 1//1 1//1 1//2".as_bytes());
+        match ObjData::load_with_options(&mut input, &options).err().unwrap() {
+            LoadingError::Parse(line,details) => {
+                assert_eq!(2,line);
+                assert_eq!(2,details.token);
+                assert!(details.message.contains("1 normals"));
+            },
+            _ => panic!(),
+        };
+
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        assert!(ObjData::load_with_options(&mut input, &options).is_ok());
+    }
+
+    #[test]
+    fn load_into() {
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::new();
+        data.load_into(&mut input).ok().unwrap();
+        assert_eq!(12,data.faces.len());
+        let capacity = data.vertices.capacity();
+
+        let obj_str =
+        r#"mtllib other.mtl
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        usemtl red
+        f 1 2 3"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        data.load_into(&mut input).ok().unwrap();
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let expected = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(expected.vertices,data.vertices);
+        assert!(data.normals.is_empty());
+        assert_eq!(expected.faces,data.faces);
+        assert_eq!(expected.objects,data.objects);
+        assert!(data.groups.is_empty());
+        assert_eq!(vec!["other.mtl"],data.material_libs);
+        assert_eq!(vec!["red"],data.materials);
+        assert_eq!(vec![Some(0)],data.face_materials);
+        assert_eq!(vec![0],data.smoothing_groups);
+        assert_eq!(vec![6],data.face_lines);
+        assert_eq!(capacity,data.vertices.capacity());
+    }
+
+    #[test]
+    fn load_lines() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        vt 0 0
+        vt 1 0
+        l 1 2 3
+        l 1/1 2/2
+        l -1 1/-1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![vec![(0,None),(1,None),(2,None)],
+                        vec![(0,Some(0)),(1,Some(1))],
+                        vec![(2,None),(0,Some(1))]],data.lines);
+        assert!(data.faces.is_empty());
+
+        for &(s,line) in [("l 1",0),("l 1/1/1 2",0)].iter() {
+            let obj_str = format!("{}\nv 0 0 0",s);
+            let mut input = BufReader::new(obj_str.as_bytes());
+            match ObjData::load(&mut input).err().unwrap() {
+                LoadingError::WrongNumberOfArguments(l) => assert!(l == line),
+                _ => panic!(),
+            }
+        }
+        let mut input = BufReader::new("l 1 a".as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(line,_) => assert!(line == 0),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn write_lines() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        vt 0 0
+        vt 1 0
+        f 1 2 3
+        l 1 2 3
+        l 1/1 2/2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        assert!(written.ends_with("f 1 2 3\nl 1 2 3\nl 1/1 2/2\n"));
+
+        let mut input = BufReader::new(written.as_bytes());
+        let reloaded = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.lines,reloaded.lines);
+
+        let mut output = Vec::new();
+        data.write_append(&mut output,IndexOffsets { vertices : 3, normals : 0, texcoords : 2 }).ok().unwrap();
+        assert!(str::from_utf8(&output).unwrap().ends_with("l 4 5 6\nl 4/3 5/4\n"));
+    }
+
+    #[test]
+    fn load_points() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        p 1 2 3
+        p -1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![vec![0,1,2],vec![2]],data.points);
+
+        let mut input = BufReader::new("p".as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(line) => assert!(line == 0),
+            _ => panic!(),
+        }
+        let mut input = BufReader::new("v 0 0 0\np 1/1".as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(line,_) => assert!(line == 1),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn write_points() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\np 1 2 3\n";
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let options = WriteOptions { omit_unit_weight : true, ..Default::default() };
+        let mut output = BufWriter::new(Vec::new());
+        data.write_with_options(&mut output,&options).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        assert_eq!(obj_str,str::from_utf8(&bytes).unwrap());
+
+        let mut output = Vec::new();
+        data.write_append(&mut output,IndexOffsets { vertices : 3, ..Default::default() }).ok().unwrap();
+        assert!(str::from_utf8(&output).unwrap().ends_with("\np 4 5 6\n"));
+    }
+
+    #[test]
+    fn load_smoothing_groups() {
+        let obj_str =
+        r#"f 1 2 3
+        s 1
+        f 1 2 3
+        s 0
+        f 1 2 3
+        s 4
+        f 1 2 3
+        s off
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![0,1,0,4,0],data.smoothing_groups);
+
+        for s in ["s garbage", "s -1", "s 1.5"].iter() {
+            let obj_str = format!("f 1 2 3\n{}\nf 1 2 3",s);
+            let mut input = BufReader::new(obj_str.as_bytes());
+            match ObjData::load(&mut input).err().unwrap() {
+                LoadingError::Parse(l,_) => assert!(l == 1),
+                _ => panic!(),
+            };
+        }
+
+        let obj_str = "s 1 2";
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(l) => assert!(l == 0),
+            _ => panic!(),
         };
     }
 
+    #[test]
+    fn write_smoothing_groups() {
+        let mut data = ObjData::new();
+        data.faces = vec![vec![(0,None,None), (1,None,None), (2,None,None)]; 4];
+        data.objects = vec![Object {
+            name : String::from(""),
+            primitives : vec![0,1,2,3]
+        }];
+        data.smoothing_groups = vec![0,2,2,0];
+        let expected =
+        r#"f 1 2 3
+s 2
+f 1 2 3
+f 1 2 3
+s off
+f 1 2 3
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
     #[test]
     fn load_object_wrong_number_of_arguments() {
         let obj_str =
@@ -537,7 +2854,7 @@ mod tests {
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
             LoadingError::WrongNumberOfArguments(line) => assert!(line == 0),
-            _ => assert!(false),
+            _ => panic!(),
         };
     }
 
@@ -642,6 +2959,456 @@ mod tests {
         assert_eq!(expected,data.groups);
     }
 
+    #[test]
+    fn load_default_group() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        f 1 2 3
+        g
+        f 1 2 3
+        f 1 2 3
+        g gr1
+        f 1 2 3
+        g
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(1,data.groups.len());
+        assert_eq!("gr1",data.groups[0].name);
+        assert_eq!(vec![3].into_iter().collect::<HashSet<usize>>(),data.groups[0].indexes);
+        assert_eq!(vec![0,1,2,4],data.default_group());
+
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        assert!(written.contains("f 1 2 3\nf 1 2 3\ng gr1\nf 1 2 3\ng\nf"));
+        let mut input = BufReader::new(written.as_bytes());
+        let reloaded = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.groups,reloaded.groups);
+        assert_eq!(data.default_group(),reloaded.default_group());
+    }
+
+    #[test]
+    fn write_ungrouped_after_grouped() {
+        let mut data = ObjData::new();
+        data.vertices = vec![(0.,0.,0.,1.), (1.,0.,0.,1.), (0.,1.,0.,1.)];
+        data.faces = vec![vec![(0,None,None), (1,None,None), (2,None,None)],
+                          vec![(0,None,None), (2,None,None), (1,None,None)]];
+        data.objects = vec![Object {name : String::new(), primitives : vec![0,1]}];
+        data.groups = vec![Group {name : String::from("a"), indexes : vec![0].into_iter().collect()}];
+
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let mut input = BufReader::new(&bytes[..]);
+        let reloaded = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.groups,reloaded.groups);
+        assert_eq!(vec![1],reloaded.default_group());
+    }
+
+    #[test]
+    fn load_materials_in_groups() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        g gr1
+        f 1 2 3
+        usemtl red
+        f 1 2 3
+        f 1 2 3
+        g gr2
+        f 1 2 3
+        usemtl blue
+        f 1 2 3
+        usemtl
+        g gr1 gr2
+        f 1 2 3
+        usemtl red
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec!["red","blue"],data.materials);
+        let materials : Vec<Option<usize>> = (0..data.faces.len()).map(|i| data.face_material(i)).collect();
+        assert_eq!(vec![None,Some(0),Some(0),Some(0),Some(1),None,Some(0)],materials);
+        assert_eq!(2,data.groups.len());
+        assert_eq!("gr1",data.groups[0].name);
+        assert_eq!(vec![0,1,2,5,6].into_iter().collect::<HashSet<usize>>(),data.groups[0].indexes);
+        assert_eq!("gr2",data.groups[1].name);
+        assert_eq!(vec![3,4,5,6].into_iter().collect::<HashSet<usize>>(),data.groups[1].indexes);
+    }
+
+    #[test]
+    fn write_materials() {
+        let obj_str =
+        r#"mtllib common.mtl
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        mtllib extra.mtl
+        usemtl red
+        f 1 2 3
+        f 1 2 3
+        usemtl blue
+        f 1 2 3
+        usemtl red
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec!["common.mtl","extra.mtl"],data.material_libs);
+        assert_eq!(vec!["red","blue"],data.materials);
+        assert_eq!(vec![Some(0),Some(0),Some(1),Some(0)],data.face_materials);
+
+        let expected =
+        r#"mtllib common.mtl
+mtllib extra.mtl
+v 0 0 0 1
+v 1 0 0 1
+v 0 1 0 1
+usemtl red
+f 1 2 3
+f 1 2 3
+usemtl blue
+f 1 2 3
+usemtl red
+f 1 2 3
+"#;
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&bytes).unwrap());
+    }
+
+    #[test]
+    fn object() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        f 1 2 3
+        o Cube
+        f 1 2 3
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![0],data.object("").unwrap().primitives);
+        assert_eq!(vec![1,2],data.object("Cube").unwrap().primitives);
+        assert!(data.object("Sphere").is_none());
+        data.object_mut("Cube").unwrap().primitives.retain(|&i| i != 1);
+        assert_eq!(vec![2],data.objects[1].primitives);
+        assert!(data.object_mut("Sphere").is_none());
+    }
+
+    #[test]
+    fn object_triangle_count() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        f 1 2 3
+        o quads
+        f 1 2 3 4
+        f 1 2 3 4 5
+        o empty"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(1),data.object_triangle_count(""));
+        assert_eq!(Some(5),data.object_triangle_count("quads"));
+        assert_eq!(Some(0),data.object_triangle_count("empty"));
+        assert_eq!(None,data.object_triangle_count("missing"));
+    }
+
+    #[test]
+    fn group() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        g gr1 gr2
+        f 1 2 3
+        g gr1
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![0,1].into_iter().collect::<HashSet<usize>>(),data.group("gr1").unwrap().indexes);
+        assert!(data.group("gr3").is_none());
+        assert!(data.group("").is_none());
+        data.group_mut("gr2").unwrap().indexes.insert(1);
+        assert_eq!(2,data.groups[1].indexes.len());
+        assert!(data.group_mut("gr3").is_none());
+    }
+
+    #[test]
+    fn load_quoted_names() {
+        let obj_str =
+        r#"o "My Object"
+        g "My Group" gr2 "gr3"
+        f 1 2 3
+        o "Cube"
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!("My Object",data.objects[0].name);
+        assert_eq!("Cube",data.objects[1].name);
+        let names : Vec<_> = data.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(vec!["My Group","gr2","gr3"],names);
+
+        let expected =
+        r#"o "My Object"
+g "My Group" gr2 gr3
+f 1 2 3
+o Cube
+f 1 2 3
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+
+        let mut input = BufReader::new(expected.as_bytes());
+        let reload = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.objects,reload.objects);
+        assert_eq!(data.groups,reload.groups);
+    }
+
+    #[test]
+    fn faces_missing_normals() {
+        let obj_str =
+        r#"f 2//1 4//1 1//1
+        f 8 6 5
+        f 4// 5//2 6//
+        f 8/3/2 6/5/3 5/7/1
+        f 9/4/ 7/3/ 3/2/"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![1,2,4],data.faces_missing_normals());
+    }
+
+    #[test]
+    fn attribute_completeness() {
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.has_normals());
+        assert!(data.all_faces_have_normals());
+        assert!(!data.has_texcoords());
+        assert!(!data.all_faces_have_texcoords());
+
+        let mut input = BufReader::new("f 1/1 2/1 3/1\nf 1/1 2 3/1".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.has_texcoords());
+        assert!(!data.all_faces_have_texcoords());
+        assert!(!data.has_normals());
+        assert!(ObjData::new().all_faces_have_normals());
+    }
+
+    #[test]
+    fn index_ranges() {
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!((0..8,0..0,0..6),data.index_ranges());
+
+        let mut input = BufReader::new("f 9/4/ 7/3/ 3/2/\nf 8 6 5".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!((2..9,1..4,0..0),data.index_ranges());
+        assert_eq!((0..0,0..0,0..0),ObjData::new().index_ranges());
+    }
+
+    #[test]
+    fn count_elements() {
+        let obj_str =
+        r#"# v 1 2 3
+        o Test
+        v 1 2 3
+        v 1 2 3
+        vn 0 0 1
+        vt 0 0
+        g gr1
+        f 1 2 3
+        l 1 2
+        p 1
+        p 2
+        g gr2
+        usemtl red
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let expected = ElementCounts {
+            vertices : 2,
+            normals : 1,
+            texcoords : 1,
+            faces : 2,
+            lines : 1,
+            points : 2,
+            objects : 1,
+            groups : 2,
+        };
+        assert_eq!(expected,ObjData::count_elements(&mut input).ok().unwrap());
+    }
+
+    #[test]
+    fn face() {
+        let f = fs::File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(12,data.face_count());
+        assert_eq!(Some(&[(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))][..]),data.face(0));
+        assert_eq!(None,data.face(100));
+        assert!(data.face_mut(100).is_none());
+        data.face_mut(11).unwrap()[0].2 = None;
+        assert_eq!((0,None,None),data.faces[11][0]);
+    }
+
+    #[test]
+    fn faces_mut() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 0
+        vt 0 0
+        f 1/1 2 3
+        f 2 4 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        for f in data.faces_mut() {
+            f[0] = (f[0].0 + 1, None, Some(0));
+        }
+        assert_eq!(vec![(1,None,Some(0)), (1,None,None), (2,None,None)],data.faces[0]);
+        assert_eq!(vec![(2,None,Some(0)), (3,None,None), (2,None,None)],data.faces[1]);
+        assert_eq!(2,data.faces_mut().count());
+    }
+
+    #[test]
+    fn load_checking_counts() {
+        let obj_str =
+        r#"# 3 vertices
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        # 2 faces
+        f 1 2 3
+        f 1 3 2
+        # 1 vertices
+        v 1 1 0
+        # 2 faces
+        f 2 4 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let (data,warnings) = ObjData::load_checking_counts(&mut input).ok().unwrap();
+        assert_eq!(3,data.faces.len());
+        assert_eq!(vec!["4 faces announced but 3 found"],warnings);
+
+        let mut input = BufReader::new("#2 vertices\n# Faces: 1\n# three faces\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+        let (_,warnings) = ObjData::load_checking_counts(&mut input).ok().unwrap();
+        assert_eq!(vec!["2 vertices announced but 3 found"],warnings);
+
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        assert!(ObjData::load_checking_counts(&mut input).ok().unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn count_comment() {
+        assert_eq!(vec![("vertices",8), ("faces",12)],super::count_comment("8 vertices, 12 faces"));
+        assert_eq!(vec![("vertices",8)],super::count_comment("Vertices: 8"));
+        assert_eq!(vec![("faces",1)],super::count_comment("Object cube 1 face"));
+        assert!(super::count_comment("Blender v2.78 (sub 0) OBJ File: ''").is_empty());
+        assert!(super::count_comment("three faces").is_empty());
+    }
+
+    #[test]
+    fn iter_groups() {
+        let obj_str =
+        r#"g gr1 gr2
+        f 2//1 4//1 1//1
+        f 8 6 5
+        g gr1
+        f 4// 5// 6//
+        f 8/3/2 6/5/3 5/7/1
+        g gr3
+        f 9/4/ 7/3/ 3/2/
+        g gr2
+        f 1 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let groups : Vec<_> = data.iter_groups().collect();
+        assert_eq!(3,groups.len());
+        assert_eq!("gr1",groups[0].0.name);
+        assert_eq!(vec![&data.faces[0],&data.faces[1],&data.faces[2],&data.faces[3]],groups[0].1);
+        assert_eq!("gr2",groups[1].0.name);
+        assert_eq!(vec![&data.faces[0],&data.faces[1],&data.faces[5]],groups[1].1);
+        assert_eq!("gr3",groups[2].0.name);
+        assert_eq!(vec![&data.faces[4]],groups[2].1);
+    }
+
+    #[test]
+    fn iter_objects() {
+        let obj_str =
+        r#"f 2//1 4//1 1//1
+        o Cube
+        f 8/3/2 6/5/3 5/7/1
+        f 9/4/ 7/3/ 3/2/"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let objects : Vec<_> = data.iter_objects().collect();
+        assert_eq!(2,objects.len());
+        assert_eq!("",objects[0].0.name);
+        assert_eq!(vec![&data.faces[0]],objects[0].1);
+        assert_eq!("Cube",objects[1].0.name);
+        assert_eq!(vec![&data.faces[1],&data.faces[2]],objects[1].1);
+    }
+
+    #[test]
+    fn write_header() {
+        let mut data = ObjData::new();
+        data.vertices = vec![(1.,-2.,-3.5,1f32)];
+        data.material_libs = vec![String::from("cube.mtl")];
+        let options = WriteOptions {
+            header : true,
+            comment : Some(String::from("Exported cube\nversion 2")),
+            ..Default::default()
+        };
+        let expected =
+        r#"# Generated by lwobj
+# Exported cube
+# version 2
+mtllib cube.mtl
+v 1 -2 -3.5 1
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write_with_options(&mut output, &options).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+
+        let options = WriteOptions {
+            header : false,
+            comment : Some(String::from("Exported cube")),
+            ..Default::default()
+        };
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write_with_options(&mut output, &options).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert!(str::from_utf8(&buf).unwrap().starts_with("# Exported cube\nmtllib"));
+    }
+
     #[test]
     fn write_vertices() {
         let mut data = ObjData::new();
@@ -662,6 +3429,26 @@ v -1 -1 -1 1
         assert_eq!(expected,str::from_utf8(&buf).unwrap());
     }
 
+    #[test]
+    fn write_omit_unit_weight() {
+        let mut data = ObjData::new();
+        data.vertices = vec![(1.,-2.,-3.5,1f32), (0.,1.,2.,0.5), (3.,2.,1.,1.)];
+        let options = WriteOptions { omit_unit_weight : true, ..Default::default() };
+        let expected =
+        r#"v 1 -2 -3.5
+v 0 1 2 0.5
+v 3 2 1
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write_with_options(&mut output, &options).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+
+        let mut input = BufReader::new(&buf[..]);
+        let reloaded = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.vertices,reloaded.vertices);
+    }
+
     #[test]
     fn write_normals() {
         let mut data = ObjData::new();
@@ -717,8 +3504,8 @@ vt 1 0 1
         data.objects = vec![obj];
         let expected =
         r#"f 2//1 4//1 1//1
-f 8// 6// 5//
-f 4// 5// 6//
+f 8 6 5
+f 4 5 6
 f 8/3/2 6/5/3 5/7/1
 f 9/4/ 7/3/ 3/2/
 "#;
@@ -728,6 +3515,208 @@ f 9/4/ 7/3/ 3/2/
         assert_eq!(expected,str::from_utf8(&buf).unwrap());
     }
 
+    #[test]
+    fn write_bare_faces() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vt 0 0
+        f 1 2 3
+        f 1// 2// 3//
+        f 1 2/1 3"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let options = WriteOptions { preserve_slashes : false, ..Default::default() };
+        let mut output = BufWriter::new(Vec::new());
+        data.write_with_options(&mut output,&options).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        assert!(written.ends_with("f 1 2 3\nf 1 2 3\nf 1// 2/1/ 3//\n"));
+    }
+
+    #[test]
+    fn write_face_slashes() {
+        for face in ["f 4// 1// 2//","f 4/ 1/ 2/","f 4 1 2","f 4 1/1 2//","f 4/1 1/1/ 2//1"].iter() {
+            let obj_str = String::from("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvn 0 0 1\n") + face + "\n";
+            let mut input = BufReader::new(obj_str.as_bytes());
+            let data = ObjData::load(&mut input).ok().unwrap();
+            for options in [WriteOptions::default(), WriteOptions { trailing_slash : false, ..Default::default() }].iter() {
+                let mut output = BufWriter::new(Vec::new());
+                data.write_with_options(&mut output,options).ok().unwrap();
+                let bytes = output.into_inner().unwrap();
+                assert_eq!(Some(*face),str::from_utf8(&bytes).unwrap().lines().last());
+            }
+        }
+
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1/ 2 3//".as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![vec![1,0,2]],data.face_slashes);
+        data.faces[0][1].2 = Some(0);
+        data.flip_winding();
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        assert!(str::from_utf8(&bytes).unwrap().ends_with("f 3// 2//1 1/\n"));
+    }
+
+    #[test]
+    fn write_explicit_default_texcoord() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let options = WriteOptions { explicit_default_texcoord : true, ..Default::default() };
+        let mut output = BufWriter::new(Vec::new());
+        assert!(data.write_with_options(&mut output, &options).is_ok());
+        let buf = output.into_inner().unwrap();
+        let text = str::from_utf8(&buf).unwrap();
+        assert_eq!(vec!["vt 0 0"],text.lines().filter(|l| l.starts_with("vt")).collect::<Vec<_>>());
+        assert!(text.contains("\nf 2/1/1 4/1/1 1/1/1\nf 8/1/2 "));
+        assert!(!text.contains("//"));
+
+        let reload = ObjData::load(&mut BufReader::new(&buf[..])).ok().unwrap();
+        for (f,r) in data.faces.iter().zip(&reload.faces) {
+            let expected : Vec<_> = f.iter().map(|&(v,_,vn)| (v,Some(0),vn)).collect();
+            assert_eq!(&expected,r);
+        }
+
+        // Nothing is added without normals
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let mut output = BufWriter::new(Vec::new());
+        assert!(data.write_with_options(&mut output, &options).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert!(!str::from_utf8(&buf).unwrap().contains("vt"));
+    }
+
+    #[test]
+    fn write_canonical() {
+        let obj_str =
+        r#"v -0 0 0
+        v 1 0 0 1
+        v 0 1 0
+        v 1 1 -0.0000001
+        vn 0 0 1
+        o Mesh
+        g gr1 gr2
+        f 1// 2// 3//
+        g gr2
+        f 2//1 4//1 3//1"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let loaded = ObjData::load(&mut input).ok().unwrap();
+
+        let mut built = ObjData::new();
+        built.vertices = vec![(0.,0.,0.,1.), (1.,0.,0.,1.), (0.,1.,0.,1.), (1.,1.,0.,1.)];
+        built.normals = vec![(0.,0.,1.)];
+        built.faces = vec![vec![(0,None,None), (1,None,None), (2,None,None)],
+                           vec![(1,None,Some(0)), (3,None,Some(0)), (2,None,Some(0))]];
+        let mut obj = Object::new(String::from("Mesh"));
+        obj.primitives = vec![0,1];
+        built.objects = vec![obj];
+        let mut gr1 = Group::new(String::from("gr1"));
+        gr1.indexes.insert(0);
+        let mut gr2 = Group::new(String::from("gr2"));
+        gr2.indexes.insert(1);
+        gr2.indexes.insert(0);
+        built.groups = vec![gr1,gr2];
+
+        let write = |data : &ObjData| {
+            let mut output = BufWriter::new(Vec::new());
+            data.write_with_options(&mut output,&WriteOptions::canonical()).ok().unwrap();
+            String::from_utf8(output.into_inner().unwrap()).unwrap()
+        };
+        let expected =
+        r#"v 0.000000 0.000000 0.000000
+v 1.000000 0.000000 0.000000
+v 0.000000 1.000000 0.000000
+v 1.000000 1.000000 0.000000
+vn 0.000000 0.000000 1.000000
+o Mesh
+g gr1 gr2
+f 1 2 3
+g gr2
+f 2//1 4//1 3//1
+"#;
+        assert_eq!(expected,write(&loaded));
+        assert_eq!(write(&loaded),write(&built));
+    }
+
+    #[test]
+    fn write_trailing_slash() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vn 0 0 1
+        f 1/1 2/2 3/1
+        f 1//1 2 3/2/1"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+
+        for &(trailing_slash,first,second) in [(true,"f 1/1/ 2/2/ 3/1/\n","f 1//1 2// 3/2/1\n"),
+                                               (false,"f 1/1 2/2 3/1\n","f 1//1 2 3/2/1\n")].iter() {
+            let options = WriteOptions { trailing_slash, preserve_slashes : false, ..Default::default() };
+            let mut output = BufWriter::new(Vec::new());
+            data.write_with_options(&mut output,&options).ok().unwrap();
+            let bytes = output.into_inner().unwrap();
+            let written = str::from_utf8(&bytes).unwrap();
+            assert!(written.ends_with(&(String::from(first) + second)));
+
+            let mut input = BufReader::new(written.as_bytes());
+            let reloaded = ObjData::load(&mut input).ok().unwrap();
+            assert_eq!(data.faces,reloaded.faces);
+        }
+    }
+
+    #[test]
+    fn write_sort_by_group() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        o Mesh
+        g gr1
+        f 1 2 3
+        g gr2
+        f 2 3 1
+        g gr1
+        f 3 1 2
+        g gr2
+        f 1 3 2
+        o Other
+        g gr1
+        f 1 2 3"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+
+        let mut output = BufWriter::new(Vec::new());
+        data.write(&mut output).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        assert_eq!(5,str::from_utf8(&bytes).unwrap().lines().filter(|l| l.starts_with("g ")).count());
+
+        let expected =
+        r#"o Mesh
+g gr1
+f 1 2 3
+f 3 1 2
+g gr2
+f 2 3 1
+f 1 3 2
+o Other
+g gr1
+f 1 2 3
+"#;
+        let options = WriteOptions { sort_by_group : true, omit_unit_weight : true, ..Default::default() };
+        let mut output = BufWriter::new(Vec::new());
+        data.write_with_options(&mut output,&options).ok().unwrap();
+        let bytes = output.into_inner().unwrap();
+        let written = str::from_utf8(&bytes).unwrap();
+        assert!(written.ends_with(expected));
+        assert_eq!(vec![0,1,2,3],data.objects[0].primitives);
+    }
+
     #[test]
     fn write_objects() {
         let mut data = ObjData::new();
@@ -748,9 +3737,9 @@ f 9/4/ 7/3/ 3/2/
         data.objects = vec![obj1,obj2];
         let expected =
         r#"f 2//1 4//1 1//1
-f 8// 6// 5//
+f 8 6 5
 o Test
-f 4// 5// 6//
+f 4 5 6
 f 8/3/2 6/5/3 5/7/1
 f 9/4/ 7/3/ 3/2/
 "#;
@@ -790,9 +3779,9 @@ f 9/4/ 7/3/ 3/2/
         let expected =
         r#"g gr1 gr2
 f 2//1 4//1 1//1
-f 8// 6// 5//
+f 8 6 5
 g gr2
-f 4// 5// 6//
+f 4 5 6
 g gr3
 f 8/3/2 6/5/3 5/7/1
 f 9/4/ 7/3/ 3/2/
@@ -802,4 +3791,61 @@ f 9/4/ 7/3/ 3/2/
         let buf = output.into_inner().unwrap();
         assert_eq!(expected,str::from_utf8(&buf).unwrap());
     }
+
+    #[test]
+    fn write_lines_points_in_context() {
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\no Mesh\ng grp\nf 1 2 3\nl 1 2\np 3\nf 3 2 1\ng other\nl 2 3\n";
+        let data = ObjData::load(&mut BufReader::new(text.as_bytes())).ok().unwrap();
+        assert_eq!(PrimitiveContext {faces_before : 1, rank : 1, object : Some(0), groups : vec![0]},data.point_contexts[0]);
+        let mut output = BufWriter::new(Vec::new());
+        let options = WriteOptions {omit_unit_weight : true, ..Default::default()};
+        assert!(data.write_with_options(&mut output, &options).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(text,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn write_split_by_object() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nv 5 5 5\nvn 0 0 1\n\
+                       o first\nf 1//1 2//1 3//1\no second\nf 2 4 3\nf 4 5 3";
+        let data = ObjData::load(&mut BufReader::new(obj_str.as_bytes())).ok().unwrap();
+        let buffers = RefCell::new(HashMap::new());
+        let result = data.write_split_by_object(|name| {
+            buffers.borrow_mut().insert(name.to_string(), Vec::new());
+            Ok(SharedBuffer {name : name.to_string(), buffers : &buffers})
+        });
+        assert!(result.is_ok());
+        let buffers = buffers.into_inner();
+        assert_eq!(2,buffers.len());
+
+        let first = ObjData::load(&mut BufReader::new(&buffers["first"][..])).ok().unwrap();
+        assert_eq!(3,first.vertices.len());
+        assert_eq!(1,first.normals.len());
+        assert_eq!(vec![vec![(0,None,Some(0)),(1,None,Some(0)),(2,None,Some(0))]],first.faces);
+        assert_eq!("first",first.objects[0].name);
+
+        let second = ObjData::load(&mut BufReader::new(&buffers["second"][..])).ok().unwrap();
+        assert_eq!(vec![(1.,0.,0.,1.),(0.,1.,0.,1.),(1.,1.,0.,1.),(5.,5.,5.,1.)],second.vertices);
+        assert!(second.normals.is_empty());
+        assert_eq!(vec![vec![(0,None,None),(2,None,None),(1,None,None)],
+                        vec![(2,None,None),(3,None,None),(1,None,None)]],second.faces);
+        assert_eq!("second",second.objects[0].name);
+    }
+
+    /// Writer appending to the buffer `name` of a shared map.
+    struct SharedBuffer<'a> {
+        name : String,
+        buffers : &'a RefCell<HashMap<String,Vec<u8>>>,
+    }
+
+    impl<'a> Write for SharedBuffer<'a> {
+        fn write(&mut self, buf : &[u8]) -> io::Result<usize> {
+            self.buffers.borrow_mut().get_mut(&self.name).unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,23 @@
+//! The types needed to load, edit and write wavefront files, to be glob imported.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::BufReader;
+//! use lwobj::prelude::*;
+//!
+//! let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\no Mesh\nf 1 2 3".as_bytes());
+//! let data : ObjData = ObjData::load(&mut input).ok().unwrap();
+//! let object : &Object = &data.objects[0];
+//! assert_eq!(object.name, "Mesh");
+//! let groups : &Vec<Group> = &data.groups;
+//! assert!(groups.is_empty());
+//!
+//! let mut input = BufReader::new("f 1 2".as_bytes());
+//! let error : LoadingError = ObjData::load(&mut input).err().unwrap();
+//! assert!(matches!(error, lwobj::LoadingError::WrongNumberOfArguments(0)));
+//! let data : lwobj::ObjData = ObjData::new();
+//! assert!(data.vertices.is_empty());
+//! ```
+
+pub use obj::{LoadingError, ObjData, Object, Group};
@@ -146,3 +146,30 @@ fn read_write_read() {
     assert_eq!(reload.objects,data.objects);
     assert_eq!(reload.groups,data.groups);
 }
+
+#[test]
+fn write_append() {
+    let f = File::open("cube.obj").unwrap();
+    let mut input = BufReader::new(f);
+    let data = ObjData::load(&mut input).ok().unwrap();
+    let mut output = Vec::new();
+    assert!(data.write_append(&mut output, IndexOffsets::default()).is_ok());
+    let offsets = IndexOffsets {
+        vertices : data.vertices.len(),
+        normals : data.normals.len(),
+        texcoords : data.texcoords.len(),
+    };
+    assert!(data.write_append(&mut output, offsets).is_ok());
+
+    let mut input = BufReader::new(&output[..]);
+    let reload = ObjData::load(&mut input).ok().unwrap();
+    assert_eq!(16,reload.vertices.len());
+    assert_eq!(12,reload.normals.len());
+    assert_eq!(24,reload.faces.len());
+    assert_eq!(data.faces[..],reload.faces[..12]);
+    for (f,r) in data.faces.iter().zip(reload.faces[12..].iter()) {
+        let shifted : Vec<_> = f.iter().map(|&(v,vt,vn)| (v+8,vt,vn.map(|n| n+6))).collect();
+        assert_eq!(&shifted,r);
+    }
+    assert_eq!(vec![String::from("Cube"),String::from("Cube")],reload.objects.iter().map(|o| o.name.clone()).collect::<Vec<_>>());
+}
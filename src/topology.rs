@@ -0,0 +1,712 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use geometry::{dot, length};
+use obj::Group;
+use obj::ObjData;
+
+/// Root of `v` in the union-find forest `parent`, compressing the path on the way.
+pub fn find_root(parent : &mut [usize], mut v : usize) -> usize {
+    while parent[v] != v {
+        parent[v] = parent[parent[v]];
+        v = parent[v];
+    }
+    v
+}
+
+impl ObjData {
+    /// List of the unique undirected edges `(a,b)` of the faces, with `a < b`, sorted.
+    pub(crate) fn unique_edges(&self) -> Vec<(usize,usize)> {
+        let mut edges = Vec::new();
+        for f in &self.faces {
+            for i in 0..f.len() {
+                let a = f[i].0;
+                let b = f[(i+1)%f.len()].0;
+                if a != b {
+                    edges.push((a.min(b),a.max(b)));
+                }
+            }
+        }
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Map each undirected edge `(a,b)`, `a < b`, to the faces using it, in increasing order.
+    ///
+    /// A face using an edge several times is listed once, and edges between a vertex and
+    /// itself are left out. Build it once to run several adjacency queries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let edges = data.build_edge_map();
+    /// assert_eq!(edges.len(), 5);
+    /// assert_eq!(edges[&(0,2)], vec![0,1]);
+    /// ```
+    pub fn build_edge_map(&self) -> HashMap<(usize,usize),Vec<usize>> {
+        let mut map : HashMap<(usize,usize),Vec<usize>> = HashMap::new();
+        for (i,f) in self.faces.iter().enumerate() {
+            for k in 0..f.len() {
+                let a = f[k].0;
+                let b = f[(k+1)%f.len()].0;
+                if a == b {
+                    continue;
+                }
+                let faces = map.entry((a.min(b),a.max(b))).or_default();
+                if faces.last() != Some(&i) {
+                    faces.push(i);
+                }
+            }
+        }
+        map
+    }
+
+    /// List the faces having an edge between the vertices `a` and `b`, in increasing order.
+    ///
+    /// The edge may be used in either direction. Build the edge map with `build_edge_map` to
+    /// query many edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.faces_on_edge(2,0), vec![0,1]);
+    /// assert_eq!(data.faces_on_edge(1,3), vec![]);
+    /// ```
+    pub fn faces_on_edge(&self, a : usize, b : usize) -> Vec<usize> {
+        self.build_edge_map().remove(&(a.min(b),a.max(b))).unwrap_or_default()
+    }
+
+    /// Map each vertex, indexed like `vertices`, to the faces using it in increasing order.
+    ///
+    /// Indices past the end of `vertices` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.build_vertex_face_map(), vec![vec![0,1], vec![0], vec![0,1], vec![1]]);
+    /// ```
+    pub fn build_vertex_face_map(&self) -> Vec<Vec<usize>> {
+        let mut map = vec![Vec::new(); self.vertices.len()];
+        for (i,f) in self.faces.iter().enumerate() {
+            for &(v,_,_) in f {
+                if let Some(faces) = map.get_mut(v) {
+                    if faces.last() != Some(&i) {
+                        faces.push(i);
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    /// List the faces using the vertex `vertex_index`, in increasing order.
+    ///
+    /// Build the map with `build_vertex_face_map` to query many vertices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.faces_using_vertex(2), vec![0,1]);
+    /// assert_eq!(data.faces_using_vertex(7), vec![]);
+    /// ```
+    pub fn faces_using_vertex(&self, vertex_index : usize) -> Vec<usize> {
+        self.build_vertex_face_map().into_iter().nth(vertex_index).unwrap_or_default()
+    }
+
+    /// List the non-manifold edges `(a,b)`, `a < b`, i.e. the edges shared by more than two faces.
+    ///
+    /// The result is sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "f 1 2 3\nf 2 1 4\nf 1 2 5";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.non_manifold_edges(), vec![(0,1)]);
+    /// ```
+    pub fn non_manifold_edges(&self) -> Vec<(usize,usize)> {
+        let mut edges : Vec<(usize,usize)> = self.build_edge_map().into_iter()
+            .filter(|(_,faces)| faces.len() > 2)
+            .map(|(e,_)| e)
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    /// Angle in radians between the normals of the two faces sharing each manifold edge `(a,b)`, `a < b`.
+    ///
+    /// Boundary and non-manifold edges are omitted, as well as the edges of degenerate faces.
+    /// Coplanar faces make an angle of `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 3 2\nf 1 2 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let angles = data.edge_dihedral_angles();
+    /// assert_eq!(angles.len(), 1);
+    /// assert!((angles[&(0,1)] - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+    /// ```
+    pub fn edge_dihedral_angles(&self) -> HashMap<(usize,usize),f32> {
+        let normals : Vec<_> = (0..self.faces.len()).map(|i| self.face_normal(i)).collect();
+        self.build_edge_map().into_iter()
+            .filter(|(_,faces)| faces.len() == 2 && faces.iter().all(|&i| length(normals[i]) > 0.))
+            .map(|(e,faces)| (e, dot(normals[faces[0]],normals[faces[1]]).clamp(-1.,1.).acos()))
+            .collect()
+    }
+
+    /// Number of connected components of the faces, faces sharing a vertex being connected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("f 1 2 3\nf 4 5 6\nf 3 4 7".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.connected_components(), 1);
+    /// ```
+    pub fn connected_components(&self) -> usize {
+        self.component_face_sets().len()
+    }
+
+    /// Partition the faces into connected components, faces sharing a vertex being connected.
+    ///
+    /// Components are ordered by their first face and list their faces in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("f 1 2 3\nf 4 5 6\nf 3 2 7".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.component_face_sets(), vec![vec![0,2], vec![1]]);
+    /// ```
+    pub fn component_face_sets(&self) -> Vec<Vec<usize>> {
+        // Union-find over the vertices
+        let mut parent : Vec<usize> = (0..self.vertices.len()).collect();
+        for f in &self.faces {
+            for &(v,_,_) in f {
+                if v >= parent.len() {
+                    parent.extend(parent.len()..v+1);
+                }
+            }
+            for k in 1..f.len() {
+                let a = find_root(&mut parent, f[0].0);
+                let b = find_root(&mut parent, f[k].0);
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+        let mut components : Vec<Vec<usize>> = Vec::new();
+        let mut roots : HashMap<usize,usize> = HashMap::new();
+        for (i,f) in self.faces.iter().enumerate() {
+            if f.is_empty() {
+                components.push(vec![i]);
+                continue;
+            }
+            let root = find_root(&mut parent, f[0].0);
+            let c = *roots.entry(root).or_insert(components.len());
+            if c == components.len() {
+                components.push(Vec::new());
+            }
+            components[c].push(i);
+        }
+        components
+    }
+
+    /// Create a group per connected component of the faces, named `prefix` followed by
+    /// the index of the component (`prefix0`, `prefix1`...).
+    ///
+    /// Faces sharing a vertex belong to the same component. Components are numbered in the
+    /// order of their first face. If a group with the same name already exists, the faces are
+    /// added to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("f 1 2 3\nf 4 5 6\nf 3 2 7".as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// data.group_by_connectivity("part");
+    /// assert_eq!(data.groups[0].name, "part0");
+    /// assert_eq!(data.groups[0].indexes.len(), 2);
+    /// assert_eq!(data.groups[1].name, "part1");
+    /// ```
+    pub fn group_by_connectivity(&mut self, prefix : &str) {
+        for (c,faces) in self.component_face_sets().into_iter().enumerate() {
+            let name = format!("{}{}",prefix,c);
+            let g = match self.groups.iter().position(|g| g.name == name) {
+                Some(g) => g,
+                None => {
+                    self.groups.push(Group::new(name));
+                    self.groups.len()-1
+                },
+            };
+            self.groups[g].indexes.extend(faces);
+        }
+    }
+
+    /// Compute the Euler characteristic `V - E + F` of the triangulated faces.
+    ///
+    /// `V` counts the vertices used by the faces, `E` the unique edges of the triangles and
+    /// `F` the triangles, the faces being triangulated as fans.
+    /// For a closed manifold mesh the genus is `(2 - χ) / 2`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let f = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.euler_characteristic(), 2);
+    /// ```
+    pub fn euler_characteristic(&self) -> i64 {
+        let triangles = self.triangles();
+        let mut vertices : Vec<usize> = triangles.iter().flat_map(|t| t.iter().cloned()).collect();
+        vertices.sort();
+        vertices.dedup();
+        let mut edges = Vec::new();
+        for t in &triangles {
+            for k in 0..3 {
+                let (a,b) = (t[k],t[(k+1)%3]);
+                edges.push((a.min(b),a.max(b)));
+            }
+        }
+        edges.sort();
+        edges.dedup();
+        vertices.len() as i64 - edges.len() as i64 + triangles.len() as i64
+    }
+
+    /// Triangles with adjacency, as used by `GL_TRIANGLES_ADJACENCY`.
+    ///
+    /// The faces are triangulated as fans. For a triangle `(a,b,c)` the record is
+    /// `[a, ab, b, bc, c, ca]`, where `ab` is the vertex opposite to the edge `(a,b)` in
+    /// another triangle sharing it. Without such a triangle, `ab` is `c`, the vertex of the
+    /// triangle itself opposite to the edge, and likewise for the other edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.triangle_adjacency(), vec![[0,2,1,0,2,3], [0,1,2,0,3,2]]);
+    /// ```
+    pub fn triangle_adjacency(&self) -> Vec<[usize;6]> {
+        let triangles = self.triangles();
+        let mut opposite : HashMap<(usize,usize),Vec<(usize,usize)>> = HashMap::new();
+        for (i,t) in triangles.iter().enumerate() {
+            for k in 0..3 {
+                let (a,b) = (t[k],t[(k+1)%3]);
+                opposite.entry((a.min(b),a.max(b))).or_default().push((i,t[(k+2)%3]));
+            }
+        }
+        triangles.iter().enumerate().map(|(i,t)| {
+            let mut record = [0;6];
+            for k in 0..3 {
+                let (a,b) = (t[k],t[(k+1)%3]);
+                let own = t[(k+2)%3];
+                record[2*k] = a;
+                record[2*k+1] = opposite[&(a.min(b),a.max(b))].iter()
+                    .find(|&&(j,_)| j != i)
+                    .map_or(own, |&(_,v)| v);
+            }
+            record
+        }).collect()
+    }
+
+    /// Number of references to each vertex in the faces, indexed like `vertices`.
+    ///
+    /// Unused vertices count `0`, and indices past the end of `vertices` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3\nf 3 2 1".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.vertex_usage_histogram(), vec![2,2,2,0]);
+    /// ```
+    pub fn vertex_usage_histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.vertices.len()];
+        for f in &self.faces {
+            for &(v,_,_) in f {
+                if let Some(count) = histogram.get_mut(v) {
+                    *count += 1;
+                }
+            }
+        }
+        histogram
+    }
+
+    /// Groups of vertices whose positions coincide within `epsilon`, without modifying them.
+    ///
+    /// Vertices closer than `epsilon` are put in the same group, transitively. Only the groups
+    /// of at least two vertices are returned, each one sorted, ordered by their first vertex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 0 0\nv 1 0 1e-7".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.duplicate_vertices(1e-5), vec![vec![0,2], vec![1,3]]);
+    /// ```
+    pub fn duplicate_vertices(&self, epsilon : f32) -> Vec<Vec<usize>> {
+        let vertices : Vec<usize> = (0..self.vertices.len()).collect();
+        self.coincident_vertices(&vertices, epsilon)
+    }
+
+    /// Groups of at least two of the given vertices whose positions coincide within `epsilon`.
+    pub(crate) fn coincident_vertices(&self, vertices : &[usize], epsilon : f32) -> Vec<Vec<usize>> {
+        let mut parent : Vec<usize> = (0..self.vertices.len()).collect();
+        let mut order = vertices.to_vec();
+        order.sort_by(|&a,&b| self.vertices[a].0.partial_cmp(&self.vertices[b].0).unwrap_or(Ordering::Equal));
+        for (k,&a) in order.iter().enumerate() {
+            let (xa,ya,za,_) = self.vertices[a];
+            for &b in &order[k+1..] {
+                let (xb,yb,zb,_) = self.vertices[b];
+                if xb - xa > epsilon {
+                    break;
+                }
+                let d = ((xb-xa)*(xb-xa) + (yb-ya)*(yb-ya) + (zb-za)*(zb-za)).sqrt();
+                if d <= epsilon {
+                    let ra = find_root(&mut parent, a);
+                    let rb = find_root(&mut parent, b);
+                    parent[ra.max(rb)] = ra.min(rb);
+                }
+            }
+        }
+        let mut groups : HashMap<usize,Vec<usize>> = HashMap::new();
+        for &v in vertices {
+            let root = find_root(&mut parent, v);
+            groups.entry(root).or_default().push(v);
+        }
+        let mut groups : Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+        for g in &mut groups {
+            g.sort();
+        }
+        groups.sort();
+        groups
+    }
+
+    /// Pairs `(i,j)`, `i < j`, of adjacent faces with inconsistent windings, sorted.
+    ///
+    /// Two faces sharing an edge are consistently wound when they traverse it in opposite
+    /// directions. The faces are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 4 3".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(data.check_winding(), vec![(0,1)]);
+    /// ```
+    pub fn check_winding(&self) -> Vec<(usize,usize)> {
+        let traverses = |i : usize, a : usize, b : usize| {
+            let f = &self.faces[i];
+            (0..f.len()).any(|k| f[k].0 == a && f[(k+1)%f.len()].0 == b)
+        };
+        let mut pairs = Vec::new();
+        for ((a,b),faces) in self.build_edge_map() {
+            for (k,&i) in faces.iter().enumerate() {
+                for &j in &faces[k+1..] {
+                    if (traverses(i,a,b) && traverses(j,a,b)) || (traverses(i,b,a) && traverses(j,b,a)) {
+                        pairs.push((i,j));
+                    }
+                }
+            }
+        }
+        pairs.sort();
+        pairs.dedup();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::BufReader;
+    use obj::*;
+
+    #[test]
+    fn euler_characteristic() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(2,data.euler_characteristic());
+
+        // A quad is split into two triangles sharing a diagonal.
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        f 1 2 3 4"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(1,data.euler_characteristic());
+    }
+
+    #[test]
+    fn triangle_adjacency() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let adjacency = data.triangle_adjacency();
+        assert_eq!(12,adjacency.len());
+        assert_eq!([1,2,3,7,0,4],adjacency[0]);
+        for (r,f) in adjacency.iter().zip(data.faces.iter()) {
+            assert_eq!([f[0].0,f[1].0,f[2].0],[r[0],r[2],r[4]]);
+            for k in 0..3 {
+                assert!(r[2*k+1] != r[0] && r[2*k+1] != r[2] && r[2*k+1] != r[4]);
+                let (a,b,c) = (r[2*k],r[(2*k+2)%6],r[2*k+1]);
+                assert!(data.faces.iter().any(|g| {
+                    let v : HashSet<usize> = g.iter().map(|c| c.0).collect();
+                    v.contains(&a) && v.contains(&b) && v.contains(&c)
+                }));
+            }
+        }
+    }
+
+    #[test]
+    fn vertex_usage_histogram() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let histogram = data.vertex_usage_histogram();
+        assert_eq!(8,histogram.len());
+        let corners : usize = data.faces.iter().map(|f| f.len()).sum();
+        assert_eq!(corners,histogram.iter().sum::<usize>());
+        assert!(histogram.iter().all(|&c| c > 0));
+
+        let mut input = BufReader::new("v 0 0 0\nf 1 1 3".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![2],data.vertex_usage_histogram());
+    }
+
+    #[test]
+    fn duplicate_vertices() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 1 0.00001 0
+        v 0 1 0
+        f 1 2 3
+        f 1 4 5"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![vec![1,3]],data.duplicate_vertices(1e-4));
+        assert!(data.duplicate_vertices(1e-6).is_empty());
+        assert_eq!(vec![vec![0,1,2,3,4]],data.duplicate_vertices(2.));
+        assert_eq!(5,data.vertices.len());
+
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.duplicate_vertices(1e-3).is_empty());
+    }
+
+    #[test]
+    fn build_edge_map() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let edges = data.build_edge_map();
+        assert_eq!(18,edges.len());
+        for (&(a,b),faces) in &edges {
+            assert!(a < b);
+            assert_eq!(2,faces.len());
+            for &i in faces {
+                assert!(data.faces[i].iter().any(|c| c.0 == a));
+                assert!(data.faces[i].iter().any(|c| c.0 == b));
+            }
+        }
+    }
+
+    #[test]
+    fn faces_on_edge() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        // Diagonal of the bottom side
+        assert_eq!(vec![0,6],data.faces_on_edge(1,3));
+        assert_eq!(vec![0,6],data.faces_on_edge(3,1));
+        // Side of the cube
+        assert_eq!(vec![0,2],data.faces_on_edge(0,1));
+        // Diagonal across the cube
+        assert!(data.faces_on_edge(0,6).is_empty());
+        assert!(data.faces_on_edge(2,2).is_empty());
+    }
+
+    #[test]
+    fn check_winding() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.check_winding().is_empty());
+
+        data.faces[4].reverse();
+        let pairs = data.check_winding();
+        assert_eq!(3,pairs.len());
+        for &(i,j) in &pairs {
+            assert!(i < j);
+            assert!(i == 4 || j == 4);
+        }
+    }
+
+    #[test]
+    fn edge_dihedral_angles() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let angles = data.edge_dihedral_angles();
+        assert_eq!(18,angles.len());
+        for (&(a,b),&angle) in &angles {
+            let (pa,pb) = (data.vertices[a],data.vertices[b]);
+            let shared = [pa.0 == pb.0, pa.1 == pb.1, pa.2 == pb.2].iter().filter(|&&s| s).count();
+            if shared == 2 {
+                // An edge of the cube
+                assert!((angle - ::std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+            } else {
+                // The diagonal of a side
+                assert!(angle.abs() < 1e-6);
+            }
+        }
+
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.edge_dihedral_angles().is_empty());
+    }
+
+    #[test]
+    fn faces_using_vertex() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![0,2,5,11],data.faces_using_vertex(0));
+        let map = data.build_vertex_face_map();
+        assert_eq!(8,map.len());
+        // Each corner of the cube is used by at least 3 of the 12 triangles, 36 corners in all
+        assert!(map.iter().all(|faces| faces.len() >= 3));
+        assert_eq!(36,map.iter().map(|faces| faces.len()).sum::<usize>());
+        assert!(data.faces_using_vertex(8).is_empty());
+
+        // A face using a vertex twice is listed once
+        let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 1 3".as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![0],data.faces_using_vertex(0));
+    }
+
+    #[test]
+    fn non_manifold_edges() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert!(data.non_manifold_edges().is_empty());
+
+        // A fin glued on the edge (1,2) of the cube.
+        data.vertices.push((0.,-2.,1.,1.));
+        data.faces.push(vec![(1,None,None), (2,None,None), (8,None,None)]);
+        assert_eq!(vec![(1,2)],data.non_manifold_edges());
+    }
+
+    #[test]
+    fn connected_components() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 5 0 0
+        v 6 0 0
+        v 5 1 0
+        f 1 2 3
+        f 4 5 6"#;
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(2,data.connected_components());
+        assert_eq!(vec![vec![0],vec![1]],data.component_face_sets());
+
+        // Second triangle sharing the edge (2,3) of the first one
+        data.faces[1] = vec![(2,None,None), (1,None,None), (4,None,None)];
+        assert_eq!(1,data.connected_components());
+        assert_eq!(vec![vec![0,1]],data.component_face_sets());
+
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(1,data.connected_components());
+        assert_eq!(0,ObjData::new().connected_components());
+    }
+
+    #[test]
+    fn group_by_connectivity() {
+        let f = File::open("cube.obj").unwrap();
+        let mut input = BufReader::new(f);
+        let mut data = ObjData::load(&mut input).ok().unwrap();
+        // Add a second cube, translated and without shared vertices.
+        let nb_vertices = data.vertices.len();
+        let vertices : Vec<_> = data.vertices.iter().map(|&(x,y,z,w)| (x+4.,y,z,w)).collect();
+        data.vertices.extend(vertices);
+        let faces : Vec<_> = data.faces.iter().map(|f| {
+            f.iter().map(|&(v,vt,vn)| (v+nb_vertices,vt,vn)).collect::<Vec<_>>()
+        }).collect();
+        data.faces.extend(faces);
+
+        data.group_by_connectivity("shell");
+        assert_eq!(5,data.groups.len());
+        assert_eq!("shell0",data.groups[3].name);
+        assert_eq!((0..12).collect::<HashSet<_>>(),data.groups[3].indexes);
+        assert_eq!("shell1",data.groups[4].name);
+        assert_eq!((12..24).collect::<HashSet<_>>(),data.groups[4].indexes);
+    }
+}